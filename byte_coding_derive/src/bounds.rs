@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{Data, DeriveInput, GenericParam, Generics, Lifetime, LifetimeDef, Type, WhereClause};
+
+use crate::byte_coding_attr::ByteCodingStructFieldAttr;
+
+/// Returns a copy of the input's generics with the where clause extended with the bounds
+/// required to implement a byte_coding trait.
+///
+/// If the user supplied a bound using one of the `bound` attributes then only that bound is
+/// added. Otherwise each type parameter that is used by a field for which `is_used` returns
+/// true is bounded by `trait_path`, so fields which are skipped by the trait don't add bounds.
+pub fn bounded_generics(
+    input: &DeriveInput,
+    user_bound: Option<&String>,
+    trait_path: TokenStream,
+    is_used: fn(&ByteCodingStructFieldAttr) -> bool,
+) -> Result<Generics, TokenStream> {
+    let mut generics = input.generics.clone();
+
+    if let Some(bound) = user_bound {
+        let where_clause = match syn::parse_str::<WhereClause>(&format!("where {}", bound)) {
+            Ok(w) => w,
+            Err(_) => {
+                return Err(quote_spanned! {input.ident.span()=>
+                    compile_error!("Unable to parse the provided bound.");
+                });
+            }
+        };
+
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(where_clause.predicates);
+
+        return Ok(generics);
+    }
+
+    let used_params = used_type_params(input, is_used)?;
+
+    let predicates: Vec<TokenStream> = input
+        .generics
+        .type_params()
+        .filter(|param| used_params.contains(&param.ident.to_string()))
+        .map(|param| {
+            let ident = &param.ident;
+
+            quote! { #ident: #trait_path }
+        })
+        .collect();
+
+    if !predicates.is_empty() {
+        let where_clause: WhereClause = syn::parse2(quote! { where #(#predicates),* }).unwrap();
+
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(where_clause.predicates);
+    }
+
+    return Ok(generics);
+}
+
+/// Adds the `'de` lifetime used by the `BorrowDecodable` trait to the generics, unless the input
+/// already declares it. The `'de` lifetime is required to outlive every other lifetime.
+pub fn with_borrow_lifetime(mut generics: Generics) -> Generics {
+    let de = Lifetime::new("'de", proc_macro2::Span::call_site());
+
+    let mut de_def = LifetimeDef::new(de.clone());
+    de_def.bounds = generics
+        .lifetimes()
+        .map(|def| def.lifetime.clone())
+        .filter(|l| *l != de)
+        .collect();
+
+    match generics.lifetimes_mut().find(|def| def.lifetime == de) {
+        Some(def) => def.bounds.extend(de_def.bounds),
+        None => generics.params.insert(0, GenericParam::Lifetime(de_def)),
+    }
+
+    return generics;
+}
+
+/// Finds the names of the type parameters which are referenced by the types of fields for which
+/// `is_used` returns true.
+fn used_type_params(
+    input: &DeriveInput,
+    is_used: fn(&ByteCodingStructFieldAttr) -> bool,
+) -> Result<BTreeSet<String>, TokenStream> {
+    let params: BTreeSet<String> = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+
+    let mut used = BTreeSet::new();
+
+    if params.is_empty() {
+        return Ok(used);
+    }
+
+    match &input.data {
+        Data::Struct(data) => {
+            for field in data.fields.iter() {
+                if !is_used(&ByteCodingStructFieldAttr::parse_attributes(&field.attrs)?) {
+                    continue;
+                }
+
+                collect_type_params(&field.ty, &params, &mut used);
+            }
+        }
+        Data::Enum(data) => {
+            for variant in data.variants.iter() {
+                for field in variant.fields.iter() {
+                    if !is_used(&ByteCodingStructFieldAttr::parse_attributes(&field.attrs)?) {
+                        continue;
+                    }
+
+                    collect_type_params(&field.ty, &params, &mut used);
+                }
+            }
+        }
+        Data::Union(_) => (),
+    }
+
+    return Ok(used);
+}
+
+fn collect_type_params(ty: &Type, params: &BTreeSet<String>, used: &mut BTreeSet<String>) {
+    fn walk(tokens: TokenStream, params: &BTreeSet<String>, used: &mut BTreeSet<String>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => {
+                    let ident = ident.to_string();
+
+                    if params.contains(&ident) {
+                        used.insert(ident);
+                    }
+                }
+                TokenTree::Group(group) => walk(group.stream(), params, used),
+                _ => (),
+            }
+        }
+    }
+
+    walk(ty.to_token_stream(), params, used);
+}
@@ -1,7 +1,7 @@
 use std::{fmt::Display, str::FromStr};
 
-use proc_macro2::TokenStream;
-use quote::quote_spanned;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::{spanned::Spanned, Attribute, DeriveInput, Lit, Meta, MetaNameValue, NestedMeta};
 
 pub const BYTE_CODING_BASE_IDENT: &'static str = "byte_coding";
@@ -12,6 +12,12 @@ pub struct ByteCodingAttr {
     pub post_enc_func: Option<String>,
     pub pre_dec_func: Option<String>,
     pub post_dec_func: Option<String>,
+    pub bound: Option<String>,
+    pub enc_bound: Option<String>,
+    pub dec_bound: Option<String>,
+    pub size: Option<usize>,
+    pub version: Option<u32>,
+    pub tagged: bool,
     pub enum_options: Option<ByteCodingEnumAttr>,
 }
 
@@ -29,7 +35,36 @@ pub struct ByteCodingEnumVariantAttr {
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct ByteCodingStructFieldAttr {
     pub order_no: Option<usize>,
+    pub tag: Option<u32>,
     pub ignore: bool,
+    pub skip_encode: bool,
+    pub skip_decode: bool,
+    pub int_encoding: Option<FieldIntEncoding>,
+    pub with: Option<String>,
+    pub encode_with: Option<String>,
+    pub decode_with: Option<String>,
+    pub since: Option<u32>,
+    pub until: Option<u32>,
+    pub default: Option<String>,
+    pub present_if: Option<String>,
+    pub length: Option<FieldLength>,
+}
+
+/// A compact encoding used for an integer field instead of its fixed width encoding.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FieldIntEncoding {
+    VarInt,
+    ZigZag,
+}
+
+/// The field which stores the length of a collection or string field, which is then encoded
+/// without its length prefix.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum FieldLength {
+    /// The number of elements, or bytes for a string.
+    Count(String),
+    /// The number of bytes the elements are encoded in.
+    ByteLen(String),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -64,6 +99,38 @@ impl ByteCodingAttr {
         };
     }
 
+    /// Returns the string of a literal which must contain a path, `error_message` is reported at
+    /// the literal if it doesn't.
+    fn lit_to_path(literal: &Lit, error_message: &str) -> Result<String, TokenStream> {
+        let s = Self::lit_to_string(literal)?;
+
+        if syn::parse_str::<syn::Path>(&s).is_err() {
+            return Err(quote_spanned! {literal.span()=>
+                compile_error!(#error_message);
+            });
+        }
+
+        return Ok(s);
+    }
+
+    fn lit_to_num<N>(literal: &Lit) -> Result<N, TokenStream>
+    where
+        N: FromStr,
+        N::Err: Display,
+    {
+        return match literal {
+            Lit::Int(n) => n.base10_parse().map_err(|_| {
+                quote_spanned! {literal.span()=>
+                    compile_error!("Invalid integer");
+                }
+            }),
+            _ => Err(quote_spanned! {
+                literal.span() =>
+                compile_error!("Expected a number.");
+            }),
+        };
+    }
+
     pub fn from_data(input: &DeriveInput) -> Result<(Self, Option<&Attribute>), TokenStream> {
         let mut toplevel_attrs: Vec<ByteCodingAttr> = Vec::new();
         let mut first_enum_attr = None;
@@ -97,6 +164,13 @@ impl ByteCodingAttr {
         merge_optionals!(self.pre_dec_func, other.pre_dec_func);
         merge_optionals!(self.post_enc_func, other.post_enc_func);
         merge_optionals!(self.post_dec_func, other.post_dec_func);
+        merge_optionals!(self.bound, other.bound);
+        merge_optionals!(self.enc_bound, other.enc_bound);
+        merge_optionals!(self.dec_bound, other.dec_bound);
+        merge_optionals!(self.size, other.size);
+        merge_optionals!(self.version, other.version);
+
+        self.tagged = self.tagged || other.tagged;
 
         if let Some(dest_enum_opts) = self.enum_options.as_mut() {
             if let Some(src_enum_opts) = other.enum_options.as_ref() {
@@ -124,6 +198,11 @@ impl ByteCodingAttr {
             "pre_dec_func" => self.pre_dec_func = Some(Self::lit_to_string(&name_value.lit)?),
             "post_enc_func" => self.post_enc_func = Some(Self::lit_to_string(&name_value.lit)?),
             "post_dec_func" => self.post_dec_func = Some(Self::lit_to_string(&name_value.lit)?),
+            "bound" => self.bound = Some(Self::lit_to_string(&name_value.lit)?),
+            "enc_bound" => self.enc_bound = Some(Self::lit_to_string(&name_value.lit)?),
+            "dec_bound" => self.dec_bound = Some(Self::lit_to_string(&name_value.lit)?),
+            "size" => self.size = Some(Self::lit_to_num(&name_value.lit)?),
+            "version" => self.version = Some(Self::lit_to_num(&name_value.lit)?),
             "encoding_type" => {
                 let variant = match Self::lit_to_string(&name_value.lit)?.as_str() {
                     "u8" => EnumEncodingType::U8,
@@ -161,6 +240,7 @@ impl ByteCodingAttr {
 
     fn set_path(&mut self, path: &syn::Path) -> Result<(), TokenStream> {
         match path.segments[0].ident.to_string().as_str() {
+            "tagged" => self.tagged = true,
             "inferred_values" => {
                 if self.enum_options.is_none() {
                     self.enum_options = Some(ByteCodingEnumAttr {
@@ -190,6 +270,12 @@ impl Default for ByteCodingAttr {
             post_enc_func: None,
             pre_dec_func: None,
             post_dec_func: None,
+            bound: None,
+            enc_bound: None,
+            dec_bound: None,
+            size: None,
+            version: None,
+            tagged: false,
             enum_options: None,
         };
     }
@@ -245,6 +331,15 @@ impl TryFrom<&Meta> for ByteCodingAttr {
     }
 }
 
+impl FieldLength {
+    /// Returns the member of the field which stores the length, its name or index.
+    pub fn field(&self) -> &str {
+        return match self {
+            FieldLength::Count(field) | FieldLength::ByteLen(field) => field,
+        };
+    }
+}
+
 impl ByteCodingEnumVariantAttr {
     pub fn new() -> Self {
         return Self::default();
@@ -371,8 +466,20 @@ impl ByteCodingStructFieldAttr {
 
     fn merge(&mut self, other: Self) {
         merge_optionals!(self.order_no, other.order_no);
+        merge_optionals!(self.tag, other.tag);
+        merge_optionals!(self.int_encoding, other.int_encoding);
+        merge_optionals!(self.with, other.with);
+        merge_optionals!(self.encode_with, other.encode_with);
+        merge_optionals!(self.decode_with, other.decode_with);
+        merge_optionals!(self.since, other.since);
+        merge_optionals!(self.until, other.until);
+        merge_optionals!(self.default, other.default);
+        merge_optionals!(self.present_if, other.present_if);
+        merge_optionals!(self.length, other.length);
 
         self.ignore = self.ignore || other.ignore;
+        self.skip_encode = self.skip_encode || other.skip_encode;
+        self.skip_decode = self.skip_decode || other.skip_decode;
     }
 
     fn lit_to_num<N>(literal: &Lit) -> Result<N, TokenStream>
@@ -403,6 +510,42 @@ impl ByteCodingStructFieldAttr {
 
         match name_value.path.segments[0].ident.to_string().as_str() {
             "order_no" => self.order_no = Some(Self::lit_to_num(&name_value.lit)?),
+            "tag" => self.tag = Some(Self::lit_to_num(&name_value.lit)?),
+            "since" => self.since = Some(Self::lit_to_num(&name_value.lit)?),
+            "until" => self.until = Some(Self::lit_to_num(&name_value.lit)?),
+            "default" => {
+                self.default = Some(ByteCodingAttr::lit_to_path(
+                    &name_value.lit,
+                    "Unable to parse the default function path.",
+                )?)
+            }
+            "with" => {
+                self.with = Some(ByteCodingAttr::lit_to_path(
+                    &name_value.lit,
+                    "Unable to parse the custom codec path.",
+                )?)
+            }
+            "present_if" => self.present_if = Some(ByteCodingAttr::lit_to_string(&name_value.lit)?),
+            "count" => {
+                let field = ByteCodingAttr::lit_to_string(&name_value.lit)?;
+                self.set_length(FieldLength::Count(field), &name_value.path)?;
+            }
+            "byte_len" => {
+                let field = ByteCodingAttr::lit_to_string(&name_value.lit)?;
+                self.set_length(FieldLength::ByteLen(field), &name_value.path)?;
+            }
+            "encode_with" => {
+                self.encode_with = Some(ByteCodingAttr::lit_to_path(
+                    &name_value.lit,
+                    "Unable to parse the custom codec path.",
+                )?)
+            }
+            "decode_with" => {
+                self.decode_with = Some(ByteCodingAttr::lit_to_path(
+                    &name_value.lit,
+                    "Unable to parse the custom codec path.",
+                )?)
+            }
             _ => {
                 return Err(quote_spanned! {
                     name_value.path.span() =>
@@ -417,6 +560,10 @@ impl ByteCodingStructFieldAttr {
     fn set_path(&mut self, path: &syn::Path) -> Result<(), TokenStream> {
         match path.segments[0].ident.to_string().as_str() {
             "ignore" => self.ignore = true,
+            "skip_encode" => self.skip_encode = true,
+            "skip_decode" => self.skip_decode = true,
+            "varint" => self.set_int_encoding(FieldIntEncoding::VarInt, path)?,
+            "zigzag" => self.set_int_encoding(FieldIntEncoding::ZigZag, path)?,
             _ => {
                 return Err(quote_spanned! {
                     path.span() =>
@@ -428,8 +575,39 @@ impl ByteCodingStructFieldAttr {
         return Ok(());
     }
 
+    fn set_int_encoding(
+        &mut self,
+        encoding: FieldIntEncoding,
+        path: &syn::Path,
+    ) -> Result<(), TokenStream> {
+        if self.int_encoding.is_some_and(|e| e != encoding) {
+            return Err(quote_spanned! {
+                path.span() =>
+                    compile_error!("Only one of varint and zigzag can be specified.");
+            });
+        }
+
+        self.int_encoding = Some(encoding);
+
+        return Ok(());
+    }
+
+    fn set_length(&mut self, length: FieldLength, path: &syn::Path) -> Result<(), TokenStream> {
+        if self.length.as_ref().is_some_and(|l| *l != length) {
+            return Err(quote_spanned! {
+                path.span() =>
+                    compile_error!("Only one of count and byte_len can be specified.");
+            });
+        }
+
+        self.length = Some(length);
+
+        return Ok(());
+    }
+
     pub fn parse_attributes(attributes: &Vec<Attribute>) -> Result<Self, TokenStream> {
         let mut working = Self::new();
+        let mut default_span = None;
 
         for attr in attributes.iter().filter(|attr| {
             attr.path.segments.len() == 1 && attr.path.segments[0].ident == BYTE_CODING_BASE_IDENT
@@ -439,12 +617,264 @@ impl ByteCodingStructFieldAttr {
                     .expect("Unable to parse meta in an enum variant attribute"),
             )?;
 
+            if n_attr.default.is_some() {
+                default_span = Some(attr.span());
+            }
+
+            if n_attr.length.is_some()
+                && working.length.is_some()
+                && n_attr.length != working.length
+            {
+                return Err(quote_spanned! {
+                    attr.span() =>
+                        compile_error!("Only one of count and byte_len can be specified.");
+                });
+            }
+
+            if n_attr.int_encoding.is_some()
+                && working.int_encoding.is_some()
+                && n_attr.int_encoding != working.int_encoding
+            {
+                return Err(quote_spanned! {
+                    attr.span() =>
+                        compile_error!("Only one of varint and zigzag can be specified.");
+                });
+            }
+
             working.merge(n_attr);
+
+            if working.int_encoding.is_some()
+                && (working.with.is_some()
+                    || working.encode_with.is_some()
+                    || working.decode_with.is_some())
+            {
+                return Err(quote_spanned! {
+                    attr.span() =>
+                        compile_error!("A field with a custom codec can't use varint or zigzag.");
+                });
+            }
+
+            if working.length.is_some()
+                && (working.int_encoding.is_some()
+                    || working.with.is_some()
+                    || working.encode_with.is_some()
+                    || working.decode_with.is_some())
+            {
+                return Err(quote_spanned! {
+                    attr.span() =>
+                        compile_error!("A field with a count or byte_len can't use varint, zigzag or a custom codec.");
+                });
+            }
+        }
+
+        // The default value would never be used
+        if let (Some(span), true) = (default_span, working.is_always_decoded()) {
+            return Err(quote_spanned! {span=>
+                compile_error!("default can only be used on a field which is ignored, skipped when decoding, conditional, versioned or tagged.");
+            });
         }
 
         return Ok(working);
     }
 
+    /// Whether the field is written by the encoder, it is not if it is ignored or skipped when
+    /// encoding.
+    pub fn is_encoded(&self) -> bool {
+        return !self.ignore && !self.skip_encode;
+    }
+
+    /// Whether the field is read by the decoder, if it is not the field is created from its
+    /// default value.
+    pub fn is_decoded(&self) -> bool {
+        return !self.ignore && !self.skip_decode;
+    }
+
+    /// Whether the field is read from all of the data, in which case its default value is never
+    /// used. A conditional, versioned or tagged field may be absent.
+    fn is_always_decoded(&self) -> bool {
+        return self.is_decoded()
+            && self.present_if.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.tag.is_none();
+    }
+
+    /// Returns the path of the function which encodes the field, if it uses a custom codec.
+    /// `encode_with` is used before the `encode` function of the `with` module.
+    pub fn encode_with_fn(&self) -> Option<TokenStream> {
+        return Self::codec_fn(self.encode_with.as_ref(), self.with.as_ref(), "encode");
+    }
+
+    /// Returns the path of the function which decodes the field, if it uses a custom codec.
+    /// `decode_with` is used before the `decode` function of the `with` module.
+    pub fn decode_with_fn(&self) -> Option<TokenStream> {
+        return Self::codec_fn(self.decode_with.as_ref(), self.with.as_ref(), "decode");
+    }
+
+    fn codec_fn(f: Option<&String>, module: Option<&String>, name: &str) -> Option<TokenStream> {
+        let f = match (f, module) {
+            (Some(f), _) => f.clone(),
+            (None, Some(module)) => format!("{}::{}", module, name),
+            (None, None) => return None,
+        };
+
+        let Ok(f_name) = syn::parse_str::<syn::Path>(&f) else {
+            unreachable!("the path is checked when the attribute is parsed");
+        };
+
+        return Some(quote! { #f_name });
+    }
+
+    /// Wraps a field value in the wrapper type used to encode it, if any.
+    pub fn wrap_int_encoding(&self, value: TokenStream) -> TokenStream {
+        return match self.int_encoding {
+            Some(FieldIntEncoding::VarInt) => quote! { ::byte_coding::VarInt(#value) },
+            Some(FieldIntEncoding::ZigZag) => quote! { ::byte_coding::ZigZag(#value) },
+            None => value,
+        };
+    }
+
+    /// Returns the type a field is decoded as, which is the wrapper type if one is used.
+    pub fn wrapped_type(&self, ty: &syn::Type) -> TokenStream {
+        return match self.int_encoding {
+            Some(FieldIntEncoding::VarInt) => quote! { ::byte_coding::VarInt<#ty> },
+            Some(FieldIntEncoding::ZigZag) => quote! { ::byte_coding::ZigZag<#ty> },
+            None => quote! { #ty },
+        };
+    }
+
+    /// Checks the `since` and `until` values against the version of the container, which is
+    /// `None` if the container is not versioned.
+    pub fn check_version(&self, version: Option<u32>, span: Span) -> Result<(), TokenStream> {
+        if self.since.is_none() && self.until.is_none() {
+            return Ok(());
+        }
+
+        let Some(version) = version else {
+            return Err(quote_spanned! {span=>
+                compile_error!("since and until can only be used when the container has a version.");
+            });
+        };
+
+        if self.since.is_some_and(|since| since > version) {
+            return Err(quote_spanned! {span=>
+                compile_error!("since is newer than the version of the container.");
+            });
+        }
+
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since > until {
+                return Err(quote_spanned! {span=>
+                    compile_error!("since is newer than until.");
+                });
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Whether the field is written by the encoder of the given container version. Fields are
+    /// always written by an unversioned container.
+    pub fn is_encoded_in(&self, version: Option<u32>) -> bool {
+        return match (version, self.until) {
+            (Some(version), Some(until)) => until >= version,
+            _ => true,
+        };
+    }
+
+    /// Returns a condition which is true when the field is present in the version of the data
+    /// being decoded, which is stored in a variable named `version`. `None` is returned if the
+    /// field is present in every version.
+    pub fn version_condition(&self) -> Option<TokenStream> {
+        return match (self.since, self.until) {
+            (Some(since), Some(until)) => Some(quote! { (#since..=#until).contains(&version) }),
+            (Some(since), None) => Some(quote! { version >= #since }),
+            (None, Some(until)) => Some(quote! { version <= #until }),
+            (None, None) => None,
+        };
+    }
+
+    /// Returns the expression which creates the value of a field that is not decoded, either
+    /// because it is not read or because it is not present in the data.
+    pub fn default_value(&self) -> TokenStream {
+        let Some(f) = &self.default else {
+            return quote! { Default::default() };
+        };
+
+        let Ok(f_name) = syn::parse_str::<syn::Path>(f) else {
+            unreachable!("the path is checked when the attribute is parsed");
+        };
+
+        return quote! { #f_name() };
+    }
+
+    /// Returns the `present_if` condition with each `self.field` replaced by the tokens `field`
+    /// returns for the member, along with the members which are used. `None` is returned if the
+    /// field is always present.
+    pub fn present_condition(
+        &self,
+        field: impl Fn(&TokenTree) -> TokenStream,
+    ) -> Result<Option<(TokenStream, Vec<String>)>, TokenStream> {
+        let Some(condition) = &self.present_if else {
+            return Ok(None);
+        };
+
+        let Ok(expr) = syn::parse_str::<syn::Expr>(condition) else {
+            return Err(quote! {
+                compile_error!("Unable to parse the present_if condition.");
+            });
+        };
+
+        let mut used = Vec::new();
+        let condition = Self::replace_self_fields(expr.to_token_stream(), &field, &mut used)?;
+
+        return Ok(Some((condition, used)));
+    }
+
+    fn replace_self_fields(
+        tokens: TokenStream,
+        field: &impl Fn(&TokenTree) -> TokenStream,
+        used: &mut Vec<String>,
+    ) -> Result<TokenStream, TokenStream> {
+        let mut res = TokenStream::new();
+        let mut tokens = tokens.into_iter();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                TokenTree::Ident(ref ident) if ident == "self" => {
+                    // The value doesn't exist while it is being decoded, only its fields do
+                    match (tokens.next(), tokens.next()) {
+                        (Some(TokenTree::Punct(dot)), Some(member))
+                            if dot.as_char() == '.'
+                                && matches!(
+                                    member,
+                                    TokenTree::Ident(_) | TokenTree::Literal(_)
+                                ) =>
+                        {
+                            res.extend(field(&member));
+                            used.push(member.to_string());
+                        }
+                        _ => {
+                            return Err(quote! {
+                                compile_error!("present_if can only use the fields of self, e.g. self.flags.");
+                            });
+                        }
+                    }
+                }
+                TokenTree::Group(group) => {
+                    let stream = Self::replace_self_fields(group.stream(), field, used)?;
+                    let mut replaced = Group::new(group.delimiter(), stream);
+                    replaced.set_span(group.span());
+
+                    res.extend([TokenTree::Group(replaced)]);
+                }
+                token => res.extend([token]),
+            }
+        }
+
+        return Ok(res);
+    }
+
     pub fn orderno_cmp(&self, other: &Self) -> std::cmp::Ordering {
         use std::cmp::Ordering;
 
@@ -466,7 +896,19 @@ impl Default for ByteCodingStructFieldAttr {
     fn default() -> Self {
         return Self {
             order_no: None,
+            tag: None,
             ignore: false,
+            skip_encode: false,
+            skip_decode: false,
+            int_encoding: None,
+            with: None,
+            encode_with: None,
+            decode_with: None,
+            since: None,
+            until: None,
+            default: None,
+            present_if: None,
+            length: None,
         };
     }
 }
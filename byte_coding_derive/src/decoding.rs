@@ -1,26 +1,49 @@
 use std::collections::BTreeSet;
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::spanned::Spanned;
-use syn::{Data, DataEnum, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Index, Path};
+use syn::{
+    Data, DataEnum, DeriveInput, Field, Fields, FieldsNamed, FieldsUnnamed, Index, Path, Type,
+};
 
 use crate::byte_coding_attr::{
     ByteCodingAttr, ByteCodingEnumVariantAttr, ByteCodingStructFieldAttr, EnumEncodingType,
+    FieldLength,
 };
-use crate::parsing::{parse_enum_variant_value, u128_to_int_tok_stream};
+use crate::parsing::{check_struct_fields, parse_enum_variant_value, u128_to_int_tok_stream};
 
-pub fn decoding(input: &DeriveInput) -> TokenStream {
+/// Generates the body of a decode method, which reads from a `Decoder` named `decoder`.
+/// `decode_fn` is the path of the function used to decode each field, e.g.
+/// `Decodable::decode_from`.
+pub fn decoding(input: &DeriveInput, decode_fn: &TokenStream) -> TokenStream {
     let (toplevel_attr, first_enum_attr) = match ByteCodingAttr::from_data(input) {
         Ok(v) => v,
         Err(s) => return s,
     };
 
+    let version = toplevel_attr.version;
+    let tagged = toplevel_attr.tagged;
+
     let body = match &input.data {
-        Data::Enum(ref data) => match generate_enum_code(&toplevel_attr, data) {
-            Ok(s) => s,
-            Err(s) => return s,
-        },
+        Data::Enum(ref data) => {
+            if version.is_some() {
+                return quote_spanned! {input.ident.span()=>
+                    compile_error!("Only structs can have a version.")
+                };
+            }
+
+            if tagged {
+                return quote_spanned! {input.ident.span()=>
+                    compile_error!("Only structs can be tagged.")
+                };
+            }
+
+            match generate_enum_code(&toplevel_attr, data, decode_fn) {
+                Ok(s) => s,
+                Err(s) => return s,
+            }
+        }
         Data::Struct(ref data) => {
             if let Some(attr) = first_enum_attr {
                 return quote_spanned! {attr.span()=>
@@ -28,16 +51,43 @@ pub fn decoding(input: &DeriveInput) -> TokenStream {
                 };
             }
 
-            match data.fields {
-                Fields::Named(ref fields) => match generate_named_struct_fields_code(fields) {
-                    Ok(s) => s,
-                    Err(s) => return s,
-                },
-                Fields::Unnamed(ref fields) => match generate_unnamed_struct_fields_code(fields) {
-                    Ok(s) => s,
-                    Err(s) => return s,
-                },
+            if let Err(s) = check_struct_fields(&data.fields, tagged) {
+                return s;
+            }
+
+            let fields = match data.fields {
+                Fields::Named(ref fields) => {
+                    match generate_named_struct_fields_code(fields, version, tagged, decode_fn) {
+                        Ok(s) => s,
+                        Err(s) => return s,
+                    }
+                }
+                Fields::Unnamed(ref fields) => {
+                    match generate_unnamed_struct_fields_code(fields, version, tagged, decode_fn) {
+                        Ok(s) => s,
+                        Err(s) => return s,
+                    }
+                }
                 Fields::Unit => quote! { let decoded_res = Self; },
+            };
+
+            // Data from older versions is accepted, newer versions may have fields which
+            // cannot be read
+            match version {
+                Some(v) => quote! {
+                    let version: ::byte_coding::VarInt<u32> = #decode_fn(decoder)?;
+                    let version = version.0;
+
+                    if version > #v {
+                        return Err(::byte_coding::DecodeError::UnsupportedVersion {
+                            version,
+                            max: #v,
+                        });
+                    }
+
+                    #fields
+                },
+                None => fields,
             }
         }
         _ => panic!("Unsupported data type"),
@@ -47,7 +97,10 @@ pub fn decoding(input: &DeriveInput) -> TokenStream {
         let f_name = syn::parse_str::<Path>(&f).unwrap();
 
         quote! {
-            buffer = #f_name (buffer)?;
+            decoder.set_remaining(::byte_coding::IntoDecodeResult::into_decode_result(
+                #f_name (decoder.try_remaining()?),
+                #f,
+            )?);
         }
     } else {
         TokenStream::new()
@@ -57,9 +110,11 @@ pub fn decoding(input: &DeriveInput) -> TokenStream {
         let f_name = syn::parse_str::<Path>(&f).unwrap();
 
         quote! {
-            let r = #f_name (decoded_res, buffer)?;
-            let res = r.0;
-            buffer = r.1;
+            let (res, buffer) = ::byte_coding::IntoDecodeResult::into_decode_result(
+                #f_name (decoded_res, decoder.try_remaining()?),
+                #f,
+            )?;
+            decoder.set_remaining(buffer);
         }
     } else {
         quote! {
@@ -74,13 +129,14 @@ pub fn decoding(input: &DeriveInput) -> TokenStream {
 
         #post_dec_func
 
-        return Some((res, buffer));
+        return Ok(res);
     };
 }
 
 fn generate_enum_code(
     toplevel_attr: &ByteCodingAttr,
     data: &DataEnum,
+    decode_fn: &TokenStream,
 ) -> Result<TokenStream, TokenStream> {
     let mut match_branches: Vec<TokenStream> = Vec::new();
     let mut found_values = BTreeSet::new();
@@ -88,37 +144,31 @@ fn generate_enum_code(
     let inferred_values = toplevel_attr.enum_options.is_some()
         && toplevel_attr.enum_options.as_ref().unwrap().inferred_values;
 
-    let mut value_parse = quote! { let res: (u16, &[u8]) = Decodable::decode_from_buf(buffer)?; };
+    // Without an encoding type the tag is read using the decoder's configuration
+    let mut value_parse = quote! { let variant_value: u16 = decoder.read_enum_tag()?; };
 
     if let Some(ref opt) = toplevel_attr.enum_options {
         if let Some(tp) = opt.encoding_type {
             value_parse = match tp {
                 EnumEncodingType::U8 => {
-                    quote! { let res: (u8, &[u8]) = Decodable::decode_from_buf(buffer)?; }
+                    quote! { let variant_value: u8 = #decode_fn(decoder)?; }
                 }
                 EnumEncodingType::U16 => {
-                    quote! { let res: (u16, &[u8]) = Decodable::decode_from_buf(buffer)?; }
+                    quote! { let variant_value: u16 = #decode_fn(decoder)?; }
                 }
                 EnumEncodingType::U32 => {
-                    quote! { let res: (u32, &[u8]) = Decodable::decode_from_buf(buffer)?; }
+                    quote! { let variant_value: u32 = #decode_fn(decoder)?; }
                 }
                 EnumEncodingType::U64 => {
-                    quote! { let res: (u64, &[u8]) = Decodable::decode_from_buf(buffer)?; }
+                    quote! { let variant_value: u64 = #decode_fn(decoder)?; }
                 }
                 EnumEncodingType::U128 => {
-                    quote! { let res: (u128, &[u8]) = Decodable::decode_from_buf(buffer)?; }
+                    quote! { let variant_value: u128 = #decode_fn(decoder)?; }
                 }
             };
         }
     }
 
-    let value_parse = quote! {
-       #value_parse
-
-       let variant_value = res.0;
-       buffer = res.1;
-    };
-
     for variant in &data.variants {
         let variant_attr = ByteCodingEnumVariantAttr::parse_attributes(&variant.attrs)?;
         last_value = if inferred_values {
@@ -151,37 +201,43 @@ fn generate_enum_code(
 
         let v = v.unwrap_or((value as u16).into_token_stream());
 
+        check_struct_fields(&variant.fields, false)?;
+
+        let mut fields_to_decode = Vec::new();
         let mut var_names = Vec::new();
         let mut is_tuple_variant = None;
 
-        let mut rhs = TokenStream::new();
-
         for (i, field) in variant.fields.iter().enumerate() {
+            let field_attr = ByteCodingStructFieldAttr::parse_attributes(&field.attrs)?;
+            field_attr.check_version(None, field.span())?;
+
             let f_ident;
+            let var_name;
 
             if let Some(n) = &field.ident {
                 f_ident = n.into_token_stream();
+                var_name = format_ident!("_{}", n);
 
                 is_tuple_variant = Some(false);
             } else {
-                f_ident = format_ident!("v{}", i).into_token_stream();
+                f_ident = Index::from(i).into_token_stream();
+                var_name = format_ident!("_{}", i);
                 is_tuple_variant = Some(true);
             }
 
-            let ty = &field.ty;
-
-            rhs = quote_spanned! {field.span()=>
-                #rhs
-
-                let res = Decodable::decode_from_buf(buffer)?;
-                let #f_ident: #ty = res.0;
-                buffer = res.1;
-            };
-
-            var_names.push(f_ident);
+            fields_to_decode.push((field_attr, field, f_ident.to_string(), var_name.clone()));
+            var_names.push((f_ident, var_name));
         }
 
+        // The fields are decoded in order_no order but constructed in declaration order
+        let decodes = decode_fields(fields_to_decode, decode_fn)?;
+        let mut rhs = quote! {
+            #decodes
+        };
+
         let variant_ident = &variant.ident;
+        let field_names = var_names.iter().map(|(f, _)| f);
+        let var_names = var_names.iter().map(|(_, v)| v);
 
         if let Some(is_tuple_variant) = is_tuple_variant {
             if is_tuple_variant {
@@ -195,7 +251,7 @@ fn generate_enum_code(
                     #rhs
 
                     Self::#variant_ident {
-                        #(#var_names),*
+                        #(#field_names: #var_names),*
                     }
                 };
             }
@@ -215,108 +271,325 @@ fn generate_enum_code(
 
         let decoded_res = match variant_value {
             #(#match_branches),*
-            _ => return None,
+            _ => return Err(::byte_coding::DecodeError::UnknownEnumTag {
+                tag: variant_value as u128,
+            }),
         };
     });
 }
 
-fn generate_unnamed_struct_fields_code(fields: &FieldsUnnamed) -> Result<TokenStream, TokenStream> {
-    let mut field_attribute_pairs = Vec::new();
+/// Generates the expression which decodes the value of a struct field, using the custom codec
+/// if there is one and decoding the wrapper type first if the field uses a compact integer
+/// encoding. A field with a `count` or `byte_len` uses the variable of its length field, which
+/// has already been decoded.
+fn decode_value(
+    field_attr: &ByteCodingStructFieldAttr,
+    ty: &Type,
+    decode_fn: &TokenStream,
+) -> Result<TokenStream, TokenStream> {
+    if let Some(f) = field_attr.decode_with_fn() {
+        return Ok(quote! { #f(decoder)? });
+    }
 
-    for (i, f) in fields.unnamed.iter().enumerate() {
-        let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
+    if let Some(length) = &field_attr.length {
+        let length_var = format_ident!("_{}", length.field());
+        let f = match length {
+            FieldLength::Count(_) => quote! { decode_count },
+            FieldLength::ByteLen(_) => quote! { decode_byte_len },
+        };
+
+        return Ok(quote! {
+            ::byte_coding::UnprefixedDecodable::#f(
+                decoder,
+                ::core::convert::TryFrom::try_from(#length_var)
+                    .map_err(|_| ::byte_coding::DecodeError::InvalidLengthField)?,
+            )?
+        });
+    }
+
+    if field_attr.int_encoding.is_none() {
+        return Ok(quote! { #decode_fn(decoder)? });
+    }
+
+    let wrapped = field_attr.wrapped_type(ty);
+
+    return Ok(quote! {
+        {
+            let wrapped: #wrapped = #decode_fn(decoder)?;
+            wrapped.0
+        }
+    });
+}
+
+/// Generates the expression which decodes a struct field. A field which is not present in every
+/// version is only decoded if it is present in the version being decoded, and a field with a
+/// `present_if` condition is only decoded if the condition is true.
+fn decode_field(
+    field_attr: &ByteCodingStructFieldAttr,
+    ty: &Type,
+    present: Option<TokenStream>,
+    decode_fn: &TokenStream,
+) -> Result<TokenStream, TokenStream> {
+    let decode = decode_value(field_attr, ty, decode_fn)?;
+
+    let condition = match (field_attr.version_condition(), present) {
+        (Some(version), Some(present)) => quote! { #version && (#present) },
+        (Some(condition), None) | (None, Some(condition)) => condition,
+        (None, None) => return Ok(decode),
+    };
+
+    let default = field_attr.default_value();
+
+    return Ok(quote! {
+        if #condition {
+            #decode
+        } else {
+            #default
+        }
+    });
+}
+
+/// Returns the `present_if` condition of a field, which can only use the variables of the fields
+/// in `decoded`, the fields which have been decoded before it.
+fn present_condition(
+    field_attr: &ByteCodingStructFieldAttr,
+    decoded: &BTreeSet<String>,
+    span: Span,
+) -> Result<Option<TokenStream>, TokenStream> {
+    let present = field_attr.present_condition(|member| {
+        let var_name = format_ident!("_{}", member.to_string());
+
+        quote! { #var_name }
+    })?;
+
+    let Some((condition, used)) = present else {
+        return Ok(None);
+    };
+
+    if let Some(member) = used.iter().find(|member| !decoded.contains(*member)) {
+        let error_message = format!(
+            "present_if can only use fields which are decoded before this one, {} is not.",
+            member
+        );
+
+        return Err(quote_spanned! {span=>
+            compile_error!(#error_message);
+        });
+    }
+
+    return Ok(Some(condition));
+}
 
+/// Generates the statements which decode fields into the variables with the given names, in
+/// order_no order. Each field is given with its member, its name or index, which is used by the
+/// `present_if` conditions of later fields. Fields which are not decoded are created from their
+/// default value.
+fn decode_fields(
+    mut fields: Vec<(ByteCodingStructFieldAttr, &Field, String, Ident)>,
+    decode_fn: &TokenStream,
+) -> Result<TokenStream, TokenStream> {
+    fields.sort_by(|(a, ..), (b, ..)| a.orderno_cmp(b));
+
+    let mut decoded = BTreeSet::new();
+    let mut statements = Vec::new();
+
+    for (field_attr, f, member, name) in fields {
         let span = f.span();
-        let index = Index::from(i);
+        let ty = &f.ty;
 
-        let res_name = format_ident!("_res_{}", index);
-        let name = format_ident!("_{}", index);
+        let decode = if field_attr.is_decoded() {
+            let present = present_condition(&field_attr, &decoded, span)?;
 
-        if field_attr.ignore {
-            field_attribute_pairs.push((
-                field_attr,
-                quote_spanned! {span=>
-                    let #name = Default::default();
-                },
-                quote_spanned! {span=>
-                    #name
-                },
-            ));
+            decode_field(&field_attr, ty, present, decode_fn)?
         } else {
-            field_attribute_pairs.push((
-                field_attr,
-                quote_spanned! {span=>
-                    let #res_name = Decodable::decode_from_buf(buffer)?;
-                    let #name = #res_name.0;
-                    buffer = #res_name.1;
-                },
-                quote_spanned! {span=>
-                    #name
-                },
-            ));
+            field_attr.default_value()
+        };
+
+        statements.push(quote_spanned! {span=>
+            let #name: #ty = #decode;
+        });
+
+        decoded.insert(member);
+    }
+
+    return Ok(quote! {
+        #(#statements)*
+    });
+}
+
+/// Generates the statements which decode the fields of a tagged struct into variables with the
+/// given names. The fields may be in any order, unknown tags are skipped and fields which are
+/// not present are created from their default value.
+fn decode_tagged_fields<'a>(
+    fields: impl Iterator<Item = (&'a Field, Ident)>,
+    version: Option<u32>,
+    decode_fn: &TokenStream,
+) -> Result<TokenStream, TokenStream> {
+    let mut slots = Vec::new();
+    let mut match_branches = Vec::new();
+    let mut values = Vec::new();
+
+    for (f, name) in fields {
+        let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
+        field_attr.check_version(version, f.span())?;
+
+        let span = f.span();
+        let ty = &f.ty;
+
+        if !field_attr.is_decoded() {
+            let default = field_attr.default_value();
+
+            values.push(quote_spanned! {span=>
+                let #name: #ty = #default;
+            });
+
+            continue;
         }
+
+        let Some(tag) = field_attr.tag else {
+            unreachable!("Every field of a tagged struct has a tag after it has been checked");
+        };
+
+        let decode = decode_value(&field_attr, ty, decode_fn)?;
+        let default = field_attr.default_value();
+
+        slots.push(quote_spanned! {span=>
+            let mut #name: Option<#ty> = None;
+        });
+        match_branches.push(quote_spanned! {span=>
+            #tag => {
+                if #name.is_some() {
+                    return Err(::byte_coding::DecodeError::DuplicateTag { tag: #tag });
+                }
+
+                #name = Some(decoder.limited(len.0, |decoder| Ok(#decode))?);
+            }
+        });
+        values.push(quote_spanned! {span=>
+            let #name: #ty = match #name {
+                Some(v) => v,
+                None => #default,
+            };
+        });
     }
 
-    field_attribute_pairs.sort_by(|(a, _, _), (b, _, _)| a.orderno_cmp(b));
+    return Ok(quote! {
+        #(#slots)*
+
+        let field_count: ::byte_coding::VarInt<usize> = #decode_fn(decoder)?;
+
+        for _ in 0..field_count.0 {
+            let tag: ::byte_coding::VarInt<u32> = #decode_fn(decoder)?;
+            let len: ::byte_coding::VarInt<usize> = #decode_fn(decoder)?;
+
+            // Unknown tags were written by a newer version of the struct
+            match tag.0 {
+                #(#match_branches)*
+                _ => decoder.skip(len.0)?,
+            }
+        }
+
+        #(#values)*
+    });
+}
 
-    let recurse = field_attribute_pairs.iter().map(|(_, s, _)| s);
-    let fields = field_attribute_pairs.iter().map(|(_, _, v)| v);
+fn generate_unnamed_struct_fields_code(
+    fields: &FieldsUnnamed,
+    version: Option<u32>,
+    tagged: bool,
+    decode_fn: &TokenStream,
+) -> Result<TokenStream, TokenStream> {
+    if tagged {
+        let names: Vec<_> = (0..fields.unnamed.len())
+            .map(|i| format_ident!("_{}", i))
+            .collect();
+        let decode =
+            decode_tagged_fields(fields.unnamed.iter().zip(names.clone()), version, decode_fn)?;
+
+        return Ok(quote! {
+            #decode
+
+            let decoded_res = Self (
+                #(#names),*
+            );
+        });
+    }
 
+    let mut fields_to_decode = Vec::new();
+    let mut names = Vec::new();
+
+    for (i, f) in fields.unnamed.iter().enumerate() {
+        let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
+        field_attr.check_version(version, f.span())?;
+
+        let name = format_ident!("_{}", i);
+
+        fields_to_decode.push((field_attr, f, i.to_string(), name.clone()));
+        names.push(name);
+    }
+
+    let decodes = decode_fields(fields_to_decode, decode_fn)?;
+
+    // The fields are decoded in order_no order but constructed in declaration order
     return Ok(quote! {
-        #(#recurse)*
+        #decodes
 
         let decoded_res = Self (
-            #(#fields),*
+            #(#names),*
         );
     });
 }
 
-fn generate_named_struct_fields_code(fields: &FieldsNamed) -> Result<TokenStream, TokenStream> {
-    let mut field_attribute_pairs = Vec::new();
+fn generate_named_struct_fields_code(
+    fields: &FieldsNamed,
+    version: Option<u32>,
+    tagged: bool,
+    decode_fn: &TokenStream,
+) -> Result<TokenStream, TokenStream> {
+    if tagged {
+        let names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+        let res_names: Vec<_> = names
+            .iter()
+            .map(|name| format_ident!("_{}", name.as_ref().unwrap()))
+            .collect();
+        let decode = decode_tagged_fields(
+            fields.named.iter().zip(res_names.clone()),
+            version,
+            decode_fn,
+        )?;
+
+        return Ok(quote! {
+            #decode
+
+            let decoded_res = Self {
+                #(#names: #res_names),*
+            };
+        });
+    }
+
+    let mut fields_to_decode = Vec::new();
+    let mut field_values = Vec::new();
 
     for f in fields.named.iter() {
         let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
-
-        let span = f.span();
+        field_attr.check_version(version, f.span())?;
 
         let name = &f.ident;
         let res_name = format_ident!("_{}", name.as_ref().unwrap());
 
-        if field_attr.ignore {
-            field_attribute_pairs.push((
-                field_attr,
-                quote_spanned! {span=>
-                    let #name = Default::default();
-                },
-                quote_spanned! {span=>
-                    #name
-                },
-            ));
-        } else {
-            field_attribute_pairs.push((
-                field_attr,
-                quote_spanned! {span=>
-                    let #res_name = Decodable::decode_from_buf(buffer)?;
-                    let #name = #res_name.0;
-                    buffer = #res_name.1;
-                },
-                quote_spanned! {span=>
-                    #name
-                },
-            ));
-        }
+        field_values.push(quote_spanned! {f.span()=>
+            #name: #res_name
+        });
+        fields_to_decode.push((field_attr, f, name.as_ref().unwrap().to_string(), res_name));
     }
 
-    field_attribute_pairs.sort_by(|(a, _, _), (b, _, _)| a.orderno_cmp(b));
-
-    let recurse = field_attribute_pairs.iter().map(|(_, s, _)| s);
-    let fields = field_attribute_pairs.iter().map(|(_, _, v)| v);
+    let decodes = decode_fields(fields_to_decode, decode_fn)?;
 
     return Ok(quote! {
-        #(#recurse)*
+        #decodes
 
         let decoded_res = Self {
-            #(#fields),*
+            #(#field_values),*
         };
     });
 }
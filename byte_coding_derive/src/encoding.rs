@@ -1,16 +1,18 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::Path;
 use syn::{
-    spanned::Spanned, Data, DataEnum, DeriveInput, FieldsNamed, FieldsUnnamed, Index, Variant,
+    spanned::Spanned, Data, DataEnum, DeriveInput, Field, Fields, FieldsNamed, FieldsUnnamed,
+    Index, Type, Variant,
 };
 
 use crate::byte_coding_attr::{
     ByteCodingAttr, ByteCodingEnumVariantAttr, ByteCodingStructFieldAttr, EnumEncodingType,
+    FieldLength,
 };
-use crate::parsing::parse_enum_variant_value;
+use crate::parsing::{check_struct_fields, parse_enum_variant_value};
 
 pub fn encoding(input: &DeriveInput) -> TokenStream {
     let (toplevel_attr, first_enum_attr) = match ByteCodingAttr::from_data(input) {
@@ -18,11 +20,28 @@ pub fn encoding(input: &DeriveInput) -> TokenStream {
         Err(s) => return s,
     };
 
+    let version = toplevel_attr.version;
+    let tagged = toplevel_attr.tagged;
+
     let body = match input.data {
-        Data::Enum(ref data) => match generate_enum_code(&toplevel_attr, data) {
-            Ok(s) => s,
-            Err(s) => return s,
-        },
+        Data::Enum(ref data) => {
+            if version.is_some() {
+                return quote_spanned! {input.ident.span()=>
+                    compile_error!("Only structs can have a version.")
+                };
+            }
+
+            if tagged {
+                return quote_spanned! {input.ident.span()=>
+                    compile_error!("Only structs can be tagged.")
+                };
+            }
+
+            match generate_enum_code(&toplevel_attr, data) {
+                Ok(s) => s,
+                Err(s) => return s,
+            }
+        }
         Data::Struct(ref data) => {
             if let Some(attr) = first_enum_attr {
                 return quote_spanned! {attr.span()=>
@@ -30,18 +49,34 @@ pub fn encoding(input: &DeriveInput) -> TokenStream {
                 };
             }
 
-            match data.fields {
-                syn::Fields::Named(ref fields) => match generate_named_struct_fields_code(fields) {
-                    Ok(s) => s,
-                    Err(s) => return s,
-                },
+            if let Err(s) = check_struct_fields(&data.fields, tagged) {
+                return s;
+            }
+
+            let fields = match data.fields {
+                syn::Fields::Named(ref fields) => {
+                    match generate_named_struct_fields_code(fields, version, tagged) {
+                        Ok(s) => s,
+                        Err(s) => return s,
+                    }
+                }
                 syn::Fields::Unnamed(ref fields) => {
-                    match generate_unnamed_struct_fields_code(fields) {
+                    match generate_unnamed_struct_fields_code(fields, version, tagged) {
                         Ok(s) => s,
                         Err(s) => return s,
                     }
                 }
                 syn::Fields::Unit => TokenStream::new(),
+            };
+
+            // A versioned struct starts with its version so older data can be recognised
+            match version {
+                Some(v) => quote! {
+                    ::byte_coding::VarInt(#v).encode_to(encoder)?;
+
+                    #fields
+                },
+                None => fields,
             }
         }
         _ => panic!("Unsupported data type"),
@@ -59,22 +94,35 @@ pub fn encoding(input: &DeriveInput) -> TokenStream {
         }
     };
 
-    let enc_post = if let Some(f) = toplevel_attr.post_enc_func {
+    // The post_enc_func hook receives the bytes of this value, so they are buffered before being
+    // written to the sink
+    if let Some(f) = toplevel_attr.post_enc_func {
         let f_name = syn::parse_str::<Path>(&f).unwrap();
 
-        quote! {
-            #f_name (buf);
-        }
-    } else {
-        TokenStream::new()
-    };
+        return quote! {
+            let mut post_enc_buf = Vec::new();
+
+            {
+                let encoder =
+                    &mut ::byte_coding::Encoder::with_config(&mut post_enc_buf, *encoder.config());
+
+                #enc_leading
+
+                #body
+            }
+
+            #f_name (&mut post_enc_buf);
+
+            return encoder.write_bytes(&post_enc_buf);
+        };
+    }
 
     return quote! {
         #enc_leading
 
         #body
 
-        #enc_post
+        return Ok(());
     };
 }
 
@@ -132,69 +180,122 @@ fn generate_enum_code(
             }
         };
 
-        let mut literal = quote! { let value: u16 = #v; };
+        // Without an encoding type the tag is written using the encoder's configuration
+        let mut tag = quote! { encoder.write_enum_tag(#v)?; };
 
         if let Some(ref opt) = toplevel_attr.enum_options {
             if let Some(tp) = opt.encoding_type {
-                literal = match tp {
+                let literal = match tp {
                     EnumEncodingType::U8 => encode_literal::<u8>(value, variant, "u8")?,
                     EnumEncodingType::U16 => encode_literal::<u16>(value, variant, "u16")?,
                     EnumEncodingType::U32 => encode_literal::<u32>(value, variant, "u32")?,
                     EnumEncodingType::U64 => encode_literal::<u64>(value, variant, "u64")?,
                     EnumEncodingType::U128 => quote! { let value: u128 = #value; },
                 };
+
+                // Encode the indicator value, scoped so it cannot shadow a field named `value`
+                tag = quote! {
+                    {
+                        #literal
+                        value.encode_to(encoder)?;
+                    }
+                };
             }
         }
 
-        let mut rhs = quote! {
-            // Encode the indicator value
-            #literal
-            value.encode_to_buf(buf);
-        };
+        check_struct_fields(&variant.fields, false)?;
 
-        let mut field_idents = Vec::new();
-        let mut is_tuple_variant = None;
+        let is_tuple_variant = matches!(variant.fields, Fields::Unnamed(_));
+        let mut variant_fields = Vec::new();
+        let mut used_members = BTreeSet::new();
+        let mut encoded_members = BTreeSet::new();
+        let mut length_members = BTreeSet::new();
 
         for (i, field) in variant.fields.iter().enumerate() {
-            let f_ident;
+            let field_attr = ByteCodingStructFieldAttr::parse_attributes(&field.attrs)?;
+            field_attr.check_version(None, field.span())?;
 
-            if let Some(n) = &field.ident {
-                f_ident = n.into_token_stream();
+            let member = match &field.ident {
+                Some(n) => n.to_string(),
+                None => i.to_string(),
+            };
+            let mut condition = None;
 
-                is_tuple_variant = Some(false);
-            } else {
-                f_ident = format_ident!("v{}", i).into_token_stream();
-                is_tuple_variant = Some(true);
+            // The fields are bound by reference, tuple fields are bound as v0, v1, etc.
+            if field_attr.is_encoded() {
+                let present = field_attr.present_condition(|member| {
+                    if is_tuple_variant {
+                        let ident = format_ident!("v{}", member.to_string());
+
+                        quote! { (*#ident) }
+                    } else {
+                        quote! { (*#member) }
+                    }
+                })?;
+
+                if let Some((present, used)) = present {
+                    used_members.extend(used);
+                    condition = Some(present);
+                }
+
+                encoded_members.insert(member.clone());
+            }
+
+            if let (true, Some(length)) = (field_attr.is_encoded(), &field_attr.length) {
+                length_members.insert(length.field().to_string());
             }
 
-            rhs = quote_spanned! {field.span()=>
-                #rhs
+            variant_fields.push((member, field, field_attr, condition));
+        }
+
+        // A length field is written using the length of another field, not its own value
+        used_members.extend(encoded_members.difference(&length_members).cloned());
+
+        let mut fields_to_encode = Vec::new();
+        let mut field_idents = Vec::new();
 
-                #f_ident.encode_to_buf(buf);
+        for (member, field, field_attr, condition) in variant_fields {
+            let ident = match &field.ident {
+                Some(n) => n.clone(),
+                None => format_ident!("v{}", member),
             };
 
-            field_idents.push(f_ident);
-        }
+            // Fields which aren't used are not bound, so they don't cause unused variable warnings
+            field_idents.push(match (&field.ident, used_members.contains(&member)) {
+                (_, true) => quote! { #ident },
+                (Some(_), false) => quote! { #ident: _ },
+                (None, false) => quote! { _ },
+            });
 
-        let variant_ident = &variant.ident;
-        let lhs;
+            if field_attr.is_encoded() {
+                let value = quote! { (*#ident) };
 
-        if let Some(is_tuple_variant) = is_tuple_variant {
-            if is_tuple_variant {
-                lhs = quote! {
-                    Self::#variant_ident (#(#field_idents),*)
-                };
-            } else {
-                lhs = quote! {
-                    Self::#variant_ident {#(#field_idents),*}
-                };
+                fields_to_encode.push((field_attr, field, member, value, condition));
             }
-        } else {
-            lhs = quote! {
-                Self::#variant_ident
-            };
         }
 
+        let fields = encode_fields(fields_to_encode)?
+            .into_iter()
+            .map(|(_, s, _)| s);
+        let rhs = quote! {
+            #tag
+
+            #(#fields)*
+        };
+
+        let variant_ident = &variant.ident;
+        let lhs = match variant.fields {
+            Fields::Named(_) => quote! {
+                Self::#variant_ident {#(#field_idents),*}
+            },
+            Fields::Unnamed(_) => quote! {
+                Self::#variant_ident (#(#field_idents),*)
+            },
+            Fields::Unit => quote! {
+                Self::#variant_ident
+            },
+        };
+
         match_branches.push(quote_spanned! {variant.span()=>
             #lhs => { #rhs }
         });
@@ -207,62 +308,247 @@ fn generate_enum_code(
     });
 }
 
-fn generate_unnamed_struct_fields_code(fields: &FieldsUnnamed) -> Result<TokenStream, TokenStream> {
-    let mut field_attribute_pairs = Vec::new();
+fn generate_unnamed_struct_fields_code(
+    fields: &FieldsUnnamed,
+    version: Option<u32>,
+    tagged: bool,
+) -> Result<TokenStream, TokenStream> {
+    let mut fields_to_encode = Vec::new();
 
     for (i, f) in fields.unnamed.iter().enumerate() {
         let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
+        field_attr.check_version(version, f.span())?;
 
-        if field_attr.ignore {
+        if !field_attr.is_encoded() || !field_attr.is_encoded_in(version) {
             continue;
         }
 
-        let span = f.span();
         let index = Index::from(i);
+        let value = quote! { data.#index };
+        let condition = present_condition(&field_attr)?;
 
-        field_attribute_pairs.push((
-            field_attr,
-            quote_spanned! {span=>
-                data.#index.encode_to_buf(buf);
-            },
-        ));
+        fields_to_encode.push((field_attr, f, i.to_string(), value, condition));
     }
 
-    field_attribute_pairs.sort_by(|(a, _), (b, _)| a.orderno_cmp(b));
-
-    let recurse = field_attribute_pairs.into_iter().map(|(_, s)| s);
-
-    return Ok(quote! {
-        #(#recurse)*
-    });
+    return Ok(join_fields_code(encode_fields(fields_to_encode)?, tagged));
 }
 
-fn generate_named_struct_fields_code(fields: &FieldsNamed) -> Result<TokenStream, TokenStream> {
-    let mut field_attribute_pairs = Vec::new();
+fn generate_named_struct_fields_code(
+    fields: &FieldsNamed,
+    version: Option<u32>,
+    tagged: bool,
+) -> Result<TokenStream, TokenStream> {
+    let mut fields_to_encode = Vec::new();
 
     for f in fields.named.iter() {
         let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
+        field_attr.check_version(version, f.span())?;
 
-        if field_attr.ignore {
+        if !field_attr.is_encoded() || !field_attr.is_encoded_in(version) {
             continue;
         }
 
+        let name = f.ident.as_ref().unwrap();
+        let value = quote! { data.#name };
+        let condition = present_condition(&field_attr)?;
+
+        fields_to_encode.push((field_attr, f, name.to_string(), value, condition));
+    }
+
+    return Ok(join_fields_code(encode_fields(fields_to_encode)?, tagged));
+}
+
+/// Generates the statements which encode the fields, sorted in order_no order. Each field is
+/// given with its member, its name or index, the expression of its value and its `present_if`
+/// condition. A length field named by a `count` or `byte_len` is written using the length of the
+/// field which names it, rather than its own value, so the two always agree. The value of a field
+/// with a `byte_len` is prepared before its length field, so it is only encoded once.
+fn encode_fields(
+    mut fields: Vec<(
+        ByteCodingStructFieldAttr,
+        &Field,
+        String,
+        TokenStream,
+        Option<TokenStream>,
+    )>,
+) -> Result<Vec<(ByteCodingStructFieldAttr, TokenStream, Option<TokenStream>)>, TokenStream> {
+    fields.sort_by(|(a, ..), (b, ..)| a.orderno_cmp(b));
+
+    let mut lengths = BTreeMap::new();
+
+    for (field_attr, _, member, value, _) in fields.iter() {
+        if let Some(length) = &field_attr.length {
+            lengths.insert(length.field().to_string(), (length, member, value));
+        }
+    }
+
+    let mut statements = Vec::new();
+
+    for (field_attr, f, member, value, condition) in fields.iter() {
         let span = f.span();
-        let name = &f.ident;
+        let encode = match lengths.get(member) {
+            Some((length, of_member, of)) => {
+                let bytes = bytes_var(of_member);
+                let value = length_value(length, of, &bytes, &f.ty, span);
+                let encode = encode_field(field_attr, value, condition.as_ref(), span)?;
+
+                match length {
+                    FieldLength::Count(_) => encode,
+                    FieldLength::ByteLen(_) => quote_spanned! {span=>
+                        let #bytes = ::byte_coding::UnprefixedBytes::new(&#of, encoder)?;
+
+                        #encode
+                    },
+                }
+            }
+            None => {
+                let value = match &field_attr.length {
+                    Some(FieldLength::ByteLen(_)) => bytes_var(member).into_token_stream(),
+                    _ => value.clone(),
+                };
+
+                encode_field(field_attr, value, condition.as_ref(), span)?
+            }
+        };
+
+        statements.push((field_attr.clone(), encode, condition.clone()));
+    }
+
+    return Ok(statements);
+}
+
+/// The variable which holds the [UnprefixedBytes] of a field with a `byte_len`.
+fn bytes_var(member: &str) -> Ident {
+    return format_ident!("_{}_bytes", member);
+}
+
+/// Generates the value of a length field, which is the length of `value` converted to the type of
+/// the length field. For a `byte_len`, `bytes` is the variable holding the prepared value.
+fn length_value(
+    length: &FieldLength,
+    value: &TokenStream,
+    bytes: &Ident,
+    ty: &Type,
+    span: Span,
+) -> TokenStream {
+    let len = match length {
+        FieldLength::Count(_) => quote! {
+            ::byte_coding::UnprefixedEncodable::count(&#value)
+        },
+        FieldLength::ByteLen(_) => quote! {
+            #bytes.len()
+        },
+    };
 
-        field_attribute_pairs.push((
-            field_attr,
-            quote_spanned! {span=>
-                data.#name.encode_to_buf(buf);
+    return quote_spanned! {span=>
+        ({
+            let length = #len;
+
+            <#ty as ::core::convert::TryFrom<usize>>::try_from(length)
+                .map_err(|_| ::byte_coding::EncodeError::LengthFieldOverflow { length })?
+        })
+    };
+}
+
+/// Returns the `present_if` condition of a struct field, which refers to the fields of the value
+/// being encoded.
+fn present_condition(
+    field_attr: &ByteCodingStructFieldAttr,
+) -> Result<Option<TokenStream>, TokenStream> {
+    return Ok(field_attr
+        .present_condition(|member| quote! { data.#member })?
+        .map(|(condition, _)| condition));
+}
+
+/// Generates the statement which encodes a field using its custom codec or compact integer
+/// encoding, if any. A field with a tag is written as part of a tagged struct, and a field with
+/// a condition is only written when the condition is true.
+fn encode_field(
+    field_attr: &ByteCodingStructFieldAttr,
+    value: TokenStream,
+    condition: Option<&TokenStream>,
+    span: Span,
+) -> Result<TokenStream, TokenStream> {
+    let encode = encode_value(field_attr, value, span)?;
+
+    return Ok(match condition {
+        Some(condition) => quote_spanned! {span=>
+            if #condition {
+                #encode
+            }
+        },
+        None => encode,
+    });
+}
+
+fn encode_value(
+    field_attr: &ByteCodingStructFieldAttr,
+    value: TokenStream,
+    span: Span,
+) -> Result<TokenStream, TokenStream> {
+    if let Some(f) = field_attr.encode_with_fn() {
+        return Ok(match field_attr.tag {
+            Some(tag) => quote_spanned! {span=>
+                encoder.write_tagged_with(#tag, |encoder| #f(&#value, encoder))?;
+            },
+            None => quote_spanned! {span=>
+                #f(&#value, encoder)?;
             },
-        ));
+        });
     }
 
-    field_attribute_pairs.sort_by(|(a, _), (b, _)| a.orderno_cmp(b));
+    // The length is stored in another field, a `byte_len` value is the prepared UnprefixedBytes
+    match field_attr.length {
+        Some(FieldLength::Count(_)) => {
+            return Ok(quote_spanned! {span=>
+                ::byte_coding::UnprefixedEncodable::encode_unprefixed(&#value, encoder)?;
+            });
+        }
+        Some(FieldLength::ByteLen(_)) => {
+            return Ok(quote_spanned! {span=>
+                #value.write_to(encoder)?;
+            });
+        }
+        None => {}
+    }
 
-    let recurse = field_attribute_pairs.into_iter().map(|(_, s)| s);
+    let value = field_attr.wrap_int_encoding(value);
 
-    return Ok(quote! {
-        #(#recurse)*
+    return Ok(match field_attr.tag {
+        Some(tag) => quote_spanned! {span=>
+            encoder.write_tagged(#tag, &#value)?;
+        },
+        None => quote_spanned! {span=>
+            #value.encode_to(encoder)?;
+        },
     });
 }
+
+/// Joins the sorted field statements, a tagged struct is prefixed by its number of fields so the
+/// decoder knows where it ends. Fields with a condition are only counted if they are written.
+fn join_fields_code(
+    field_attribute_pairs: Vec<(ByteCodingStructFieldAttr, TokenStream, Option<TokenStream>)>,
+    tagged: bool,
+) -> TokenStream {
+    let count = field_attribute_pairs
+        .iter()
+        .filter(|(_, _, condition)| condition.is_none())
+        .count();
+    let conditions: Vec<_> = field_attribute_pairs
+        .iter()
+        .filter_map(|(_, _, condition)| condition.clone())
+        .collect();
+    let recurse = field_attribute_pairs.into_iter().map(|(_, s, _)| s);
+
+    if tagged {
+        return quote! {
+            ::byte_coding::VarInt(#count #(+ usize::from(#conditions))*).encode_to(encoder)?;
+
+            #(#recurse)*
+        };
+    }
+
+    return quote! {
+        #(#recurse)*
+    };
+}
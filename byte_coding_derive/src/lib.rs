@@ -1,4 +1,5 @@
-//! Derive macros for the Decodable and Encodable traits provided by the byte_coding crate.
+//! Derive macros for the Decodable, BorrowDecodable, Encodable and FixedSize traits provided by
+//! the byte_coding crate.
 //!
 //! # Usage
 //! It is not recommended to include this crate directly, instead use the 'derive' feature
@@ -10,13 +11,18 @@
 //! byte_coding = { git = "https://github.com/aidos9/byte_coding", features = ["derive"] }
 //! ```
 
+mod bounds;
 mod byte_coding_attr;
 mod decoding;
 mod encoding;
 mod parsing;
+mod sizing;
 
+use bounds::{bounded_generics, with_borrow_lifetime};
+use byte_coding_attr::{ByteCodingAttr, ByteCodingStructFieldAttr};
 use decoding::decoding;
 use encoding::encoding;
+use sizing::fixed_size;
 
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
@@ -36,6 +42,21 @@ use syn::{parse_macro_input, DeriveInput};
 /// * `post_dec_func` - A string which contains the name of a function which should perform
 /// some operation on the decoded data before it is returned.
 /// e.g. `#[byte_coding(post_dec_func = "my_func")]`
+/// * `bound` - A string containing where clause predicates which replace the inferred bounds
+/// on the generated implementation. By default every type parameter used by a field that is
+/// decoded is required to implement `Decodable`. This value is used by both derive macros.
+/// e.g. `#[byte_coding(bound = "T: Decodable + Clone")]`
+/// * `dec_bound` - The same as `bound` but only used by the `Decodable` derive macro, if both
+/// are provided this value is used.
+/// e.g. `#[byte_coding(dec_bound = "T: Decodable + Clone")]`
+/// * `version` - Structs only. The current version of the struct's format, the data starts
+/// with the version it was encoded with. Data from older versions can be decoded, but a newer
+/// version is rejected with `DecodeError::UnsupportedVersion`.
+/// e.g. `#[byte_coding(version = 2)]`
+/// * `tagged` - Structs only. Each field is read as a tag, a length and a value, in any order.
+/// Fields with unknown tags are skipped and fields which are not present are created from their
+/// default value.
+/// e.g. `#[byte_coding(tagged)]`
 ///
 /// #### Struct Fields
 /// * `order_no` - An integer to indicate the order in which the field should be encoded.
@@ -43,14 +64,63 @@ use syn::{parse_macro_input, DeriveInput};
 /// have a value. However, no value can be repeated and any fields without order numbers
 /// will be processed from top to bottom after any fields with order numbers.
 /// e.g. `#[byte_coding(order_no = 0)]`
-/// * `ignore` - Specify this option to ignore decoding this field, this value must have the
-/// Default trait implemented.
-/// e.g. `#[byte_coding(ignore)]`
+/// * `tag` - The number which identifies a field of a tagged struct, every field which is
+/// encoded or decoded needs a tag and no tag can be repeated.
+/// e.g. `#[byte_coding(tag = 7)]`
+/// * `ignore` - Specify this option to ignore decoding this field, it is created from its
+/// default value instead. The type must implement `Default` unless `default` is provided.
+/// e.g. `#[byte_coding(ignore)]` or `#[byte_coding(ignore, default = "my_func")]`
+/// * `skip_decode` - The same as `ignore`, but the field is still encoded. This is intended for
+/// fields which are computed after decoding, such as by the `post_dec_func`. Since the field is
+/// written but not read, the data can only be decoded by a type which expects the field.
+/// e.g. `#[byte_coding(skip_decode)]`
+/// * `skip_encode` - Only used by the `Encodable` derive macro, the field is still decoded.
+/// e.g. `#[byte_coding(skip_encode)]`
+/// * `varint` - Decode an integer field as a LEB128 variable length integer, see `VarInt`.
+/// e.g. `#[byte_coding(varint)]`
+/// * `zigzag` - Decode a signed integer field using zigzag encoding, see `ZigZag`. Only one of
+/// `varint` and `zigzag` can be specified. A generic field using either option needs a `bound`.
+/// e.g. `#[byte_coding(zigzag)]`
+/// * `since` - The first version of a versioned struct which contains this field. When older
+/// data is decoded the field is created from its default value.
+/// e.g. `#[byte_coding(since = 2)]`
+/// * `until` - The last version of a versioned struct which contains this field. When newer
+/// data is decoded the field is created from its default value.
+/// e.g. `#[byte_coding(until = 1)]`
+/// * `default` - A string which contains the name of a function that creates the value of a
+/// field which is not decoded, because it is ignored or skipped, its `present_if` condition is
+/// false, or it is not present in the version being decoded or in the data of a tagged struct.
+/// `Default` is used otherwise. It can't be used on a field which is always decoded.
+/// e.g. `#[byte_coding(default = "my_func")]`
+/// * `present_if` - A string which contains a condition, the field is only decoded when it is
+/// true. `self.field` in the condition refers to a field which has already been decoded, a
+/// predicate function can be called with the fields it needs. The condition is not used by a
+/// tagged struct, whose fields are present when their tag is.
+/// e.g. `#[byte_coding(present_if = "self.flags & 0x4 != 0")]` or
+/// `#[byte_coding(present_if = "has_checksum(self.flags)")]`
+/// * `count` - A string which contains the name of an integer field that stores the length of
+/// this field, which is read without its length prefix. The field must be a `Vec`, `String`,
+/// `&str` or `&[u8]`, the length of a string is its number of bytes. The length field must be
+/// decoded before this one, and can't be skipped, conditional or versioned. It can't be used in
+/// a tagged struct.
+/// e.g. `#[byte_coding(count = "n_items")]`
+/// * `byte_len` - The same as `count`, but the length field stores the number of bytes the
+/// elements were encoded in. Elements are read until exactly that many bytes have been read.
+/// Only one of `count` and `byte_len` can be specified.
+/// e.g. `#[byte_coding(byte_len = "payload_len")]`
+/// * `with` - A string which contains the path of a module whose `decode` function is used to
+/// decode this field instead of its `Decodable` implementation, such as for a foreign type. It
+/// can't be combined with `varint` or `zigzag`, and a generic field using it needs a `bound`.
+/// e.g. `#[byte_coding(with = "my_module")]`
+/// * `decode_with` - A string which contains the path of the function used to decode this
+/// field, it is used instead of the `decode` function of the `with` module.
+/// e.g. `#[byte_coding(decode_with = "my_func")]`
 ///
 /// #### Enums
 /// * `encoding_type` - A string which indicates what type the enum variant values are,
-/// by default a 'u16' value is used. If a smaller or larger value is required it should
-/// be annotated here. Supported values: `["u8", "u16", "u32", "u64", "u128"]`.
+/// by default the enum tag encoding of the `Config` is used, which is a 'u16' value unless
+/// configured otherwise. If a specific smaller or larger type is required it should be
+/// annotated here. Supported values: `["u8", "u16", "u32", "u64", "u128"]`.
 /// e.g. `#[byte_coding(encoding_type = "u64")]`
 /// * `inferred_values` - A flag which when set indicates the byte_coding can infer values,
 /// by default these are numeric values starting at zero and increasing by 1 for each variant.
@@ -64,6 +134,11 @@ use syn::{parse_macro_input, DeriveInput};
 /// is not provided, instead this attribute must be set to inform the macro the values
 /// it should assign to each enum variant. e.g. `#[byte_coding(value = 1)]`
 ///
+/// #### Enum Variant Fields
+/// The fields of tuple and struct variants accept the same values as struct fields, except for
+/// `tag`, `since` and `until`. Each variant's fields are ordered and ignored independently.
+/// e.g. `#[byte_coding(order_no = 0)]`
+///
 /// # Examples
 /// ### Simple Examples
 /// Most direct serialisation and deserialisation should be able to be achieved without
@@ -224,6 +299,249 @@ use syn::{parse_macro_input, DeriveInput};
 /// assert_eq!(decoded, [ExampleEnum::EB, ExampleEnum::EA, ExampleEnum::EC]);
 /// ```
 ///
+/// ### Versioning
+/// A struct with a version can gain and lose fields without breaking the decoding of data
+/// written by older versions. In the below example the `name` field was added in version 2.
+///
+/// ```
+/// # use byte_coding::{Decodable, DecodeError, Encodable, VarInt};
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Encodable, Decodable)]
+/// #[byte_coding(version = 2)]
+/// struct Account {
+///     id: u32,
+///     #[byte_coding(since = 2, default = "unnamed")]
+///     name: String,
+/// }
+///
+/// fn unnamed() -> String {
+///     return "unnamed".to_string();
+/// }
+///
+/// // Data written by version 1
+/// let encoded = vec![1, 7, 0, 0, 0];
+///
+/// assert_eq!(
+///     Account::decode(&encoded),
+///     Ok(Account { id: 7, name: "unnamed".to_string() })
+/// );
+///
+/// // Data written by a future version
+/// let encoded = vec![3, 7, 0, 0, 0];
+///
+/// assert_eq!(
+///     Account::decode(&encoded),
+///     Err(DecodeError::UnsupportedVersion { version: 3, max: 2 })
+/// );
+/// ```
+///
+/// ### Tagged Structs
+/// A tagged struct can also be read by older versions of itself, since each field is written
+/// with its tag and length. In the below example an older reader skips the `host` field, which
+/// it does not know, and creates the `timeout` field, which is not present, from its default. A
+/// tag which appears more than once is rejected with `DecodeError::DuplicateTag`.
+///
+/// ```
+/// # use byte_coding::{Decodable, Encodable};
+///
+/// #[derive(Encodable)]
+/// #[byte_coding(tagged)]
+/// struct Settings {
+///     #[byte_coding(tag = 1)]
+///     port: u16,
+///     #[byte_coding(tag = 3)]
+///     host: String,
+/// }
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Decodable)]
+/// #[byte_coding(tagged)]
+/// struct OldSettings {
+///     #[byte_coding(tag = 1)]
+///     port: u16,
+///     #[byte_coding(tag = 2, default = "default_timeout")]
+///     timeout: u32,
+/// }
+///
+/// fn default_timeout() -> u32 {
+///     return 30;
+/// }
+///
+/// let encoded = Settings { port: 80, host: "a".to_string() }.encoded();
+///
+/// assert_eq!(
+///     OldSettings::decode(&encoded),
+///     Ok(OldSettings { port: 80, timeout: 30 })
+/// );
+/// ```
+///
+/// ### Conditional Fields
+/// A field can depend on a field which was decoded before it, such as a flag. When the condition
+/// is false the field is not read and is created from its default value.
+///
+/// ```
+/// # use byte_coding::{Decodable, Encodable};
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Encodable, Decodable)]
+/// struct Packet {
+///     flags: u8,
+///     #[byte_coding(present_if = "self.flags & 0x4 != 0")]
+///     checksum: u16,
+///     #[byte_coding(present_if = "has_sender(self.flags)")]
+///     sender: Option<u32>,
+/// }
+///
+/// fn has_sender(flags: u8) -> bool {
+///     return flags & 0x1 != 0;
+/// }
+///
+/// let value = Packet { flags: 0x4, checksum: 7, sender: None };
+/// let encoded = value.encoded();
+///
+/// assert_eq!(encoded, vec![0x4, 7, 0]);
+/// assert_eq!(Packet::decode(&encoded), Ok(value));
+/// ```
+///
+/// A `default` is rejected on a field which is always decoded, since it would never be used.
+/// ```compile_fail
+/// # use byte_coding::Decodable;
+///
+/// #[derive(Decodable)]
+/// struct Packet {
+///     #[byte_coding(default = "default_flags")]
+///     flags: u8,
+/// }
+///
+/// fn default_flags() -> u8 {
+///     return 1;
+/// }
+/// ```
+///
+/// ### Length Fields
+/// Many formats store the length of a collection in an earlier field, rather than before the
+/// elements. The length field is written using the length of the collection, so its own value
+/// doesn't need to be kept up to date. A length which doesn't fit in the length field is an
+/// error, so `encoded` panics and `encoded_with_config` should be used when that is possible.
+///
+/// ```
+/// # use byte_coding::{Config, Decodable, Encodable, EncodeError};
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Encodable, Decodable)]
+/// struct Message {
+///     n_items: u8,
+///     payload_len: u16,
+///     #[byte_coding(count = "n_items")]
+///     items: Vec<u16>,
+///     #[byte_coding(byte_len = "payload_len")]
+///     payload: String,
+/// }
+///
+/// let value = Message {
+///     n_items: 0,
+///     payload_len: 0,
+///     items: vec![1, 2],
+///     payload: "ab".to_string(),
+/// };
+/// let encoded = value.encoded();
+///
+/// assert_eq!(encoded, vec![2, 2, 0, 1, 0, 2, 0, b'a', b'b']);
+/// assert_eq!(
+///     Message::decode(&encoded),
+///     Ok(Message { n_items: 2, payload_len: 2, ..value })
+/// );
+///
+/// let value = Message {
+///     n_items: 0,
+///     payload_len: 0,
+///     items: vec![0; 256],
+///     payload: String::new(),
+/// };
+///
+/// assert_eq!(
+///     value.encoded_with_config(Config::default()),
+///     Err(EncodeError::LengthFieldOverflow { length: 256 })
+/// );
+/// ```
+///
+/// ### Custom Codecs
+/// A field whose type doesn't implement the byte_coding traits, and can't because of the orphan
+/// rule, can be given a module of functions with the below signatures, where T is the type of
+/// the field. The `Decoder` of a `BorrowDecodable` implementation has the `'de` lifetime.
+/// ```ignore
+/// fn encode(value: &T, encoder: &mut Encoder<'_>) -> Result<(), EncodeError>
+/// fn decode(decoder: &mut Decoder<'_>) -> Result<T, DecodeError>
+/// ```
+///
+/// ```
+/// # use byte_coding::{Decodable, Encodable};
+/// use std::time::Duration;
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Encodable, Decodable)]
+/// struct Timeout {
+///     #[byte_coding(with = "millis")]
+///     after: Duration,
+/// }
+///
+/// mod millis {
+///     use byte_coding::{Decodable, DecodeError, Decoder, Encodable, EncodeError, Encoder};
+///     use std::time::Duration;
+///
+///     pub fn encode(value: &Duration, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+///         return (value.as_millis() as u64).encode_to(encoder);
+///     }
+///
+///     pub fn decode(decoder: &mut Decoder<'_>) -> Result<Duration, DecodeError> {
+///         return Ok(Duration::from_millis(u64::decode_from(decoder)?));
+///     }
+/// }
+///
+/// let value = Timeout { after: Duration::from_secs(1) };
+/// let encoded = value.encoded();
+///
+/// assert_eq!(encoded, 1000u64.encoded());
+/// assert_eq!(Timeout::decode(&encoded), Ok(value));
+/// ```
+///
+/// The generated implementation reads from the `Decoder` passed to `decode_from`, so the
+/// decoder's limits apply to every field. Each decoded value of the type also counts as one
+/// level of nesting, which stops recursive types from exhausting the stack.
+///
+/// Generic types, lifetimes and const generics are supported. Each type parameter used by a
+/// field is required to implement `Decodable`, if a different bound is needed it can be provided
+/// with the `bound` or `dec_bound` key.
+///
+/// ```
+/// # use byte_coding::Decodable;
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Decodable)]
+/// struct Envelope<T, const N: usize> {
+///     id: [u8; N],
+///     body: T
+/// }
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Decodable)]
+/// #[byte_coding(dec_bound = "T: Decodable + Clone + 'static")]
+/// struct Batch<T> {
+///     items: Vec<T>
+/// }
+///
+/// let encoded = vec![1, 2, 5, 0];
+/// let decoded: Envelope<u16, 2> = Decodable::decode(&encoded).unwrap();
+///
+/// assert_eq!(decoded, Envelope { id: [1, 2], body: 5 });
+///
+/// let encoded = vec![1, 0, 0, 0, 0, 0, 0, 0, 3];
+/// let decoded: Batch<u8> = Decodable::decode(&encoded).unwrap();
+///
+/// assert_eq!(decoded, Batch { items: vec![3] });
+/// ```
+///
 /// Additionally if you want to perform some operation on the data or result before or after
 /// decoding, you can set a function the `pre_dec_func` key and the `post_dec_func` key.
 ///
@@ -239,6 +557,9 @@ use syn::{parse_macro_input, DeriveInput};
 /// fn post_dec_func(value: T, buffer: &[u8]) -> Option<(T, &[u8])>;
 /// ```
 ///
+/// Either function may instead return a `Result` with a `DecodeError` as the error type. A
+/// `None` value returned from either function is reported as a `DecodeError::HookFailed` error.
+///
 /// In the below example we change the f3 value to a None value if the decoded struct has a 0
 /// length string.
 /// ```
@@ -272,13 +593,96 @@ use syn::{parse_macro_input, DeriveInput};
 pub fn decodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
-    let decoding_calls = decoding(&input);
-    let name = input.ident;
+    let decoding_calls = decoding(&input, &quote! { Decodable::decode_from });
+    let generics = match ByteCodingAttr::from_data(&input).and_then(|(attr, _)| {
+        bounded_generics(
+            &input,
+            attr.dec_bound.as_ref().or(attr.bound.as_ref()),
+            quote! { Decodable },
+            ByteCodingStructFieldAttr::is_decoded,
+        )
+    }) {
+        Ok(g) => g,
+        Err(s) => return proc_macro::TokenStream::from(s),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let name = &input.ident;
 
     let expanded = quote! {
-        impl Decodable for #name {
-            fn decode_from_buf(mut buffer: &[u8]) -> Option<(Self, &[u8])> {
-                #decoding_calls
+        impl #impl_generics Decodable for #name #ty_generics #where_clause {
+            fn decode_from(
+                decoder: &mut ::byte_coding::Decoder<'_>,
+            ) -> Result<Self, ::byte_coding::DecodeError> {
+                return decoder.nested(|decoder| {
+                    #decoding_calls
+                });
+            }
+        }
+    };
+
+    return proc_macro::TokenStream::from(expanded);
+}
+
+/// Generates an implementation of the BorrowDecodable trait for a data type.
+///
+/// The generated implementation decodes the same format as the `Decodable` derive macro, and
+/// supports the same attributes, but each field is decoded using `BorrowDecodable`. This allows
+/// fields such as `&str`, `&[u8]` and `Cow<str>` to borrow directly from the input buffer.
+///
+/// The implementation is generic over the lifetime of the input buffer, which is named `'de`.
+/// If the type declares a `'de` lifetime it is used as the input lifetime, otherwise `'de` is
+/// added and required to outlive each of the type's lifetimes. Each type parameter used by a
+/// field is required to implement `BorrowDecodable<'de>`, the `bound` and `dec_bound` keys can
+/// refer to the `'de` lifetime when a different bound is needed.
+///
+/// ```
+/// # use byte_coding::{BorrowDecodable, Encodable};
+/// # use std::borrow::Cow;
+///
+/// # #[derive(Debug, PartialEq)]
+/// #[derive(Encodable, BorrowDecodable)]
+/// struct Message<'a> {
+///     id: u16,
+///     name: &'a str,
+///     payload: Option<&'a [u8]>,
+///     note: Cow<'a, str>
+/// }
+///
+/// let message = Message { id: 1, name: "a", payload: Some(&[1, 2]), note: "b".into() };
+/// let encoded = message.encoded();
+/// let decoded: Message = BorrowDecodable::borrow_decode(&encoded).unwrap();
+///
+/// assert_eq!(decoded, message);
+/// assert!(matches!(decoded.note, Cow::Borrowed(_)));
+/// ```
+#[proc_macro_derive(BorrowDecodable, attributes(byte_coding))]
+pub fn borrow_decodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let decoding_calls = decoding(&input, &quote! { BorrowDecodable::borrow_decode_from });
+    let generics = match ByteCodingAttr::from_data(&input).and_then(|(attr, _)| {
+        bounded_generics(
+            &input,
+            attr.dec_bound.as_ref().or(attr.bound.as_ref()),
+            quote! { BorrowDecodable<'de> },
+            ByteCodingStructFieldAttr::is_decoded,
+        )
+    }) {
+        Ok(g) => with_borrow_lifetime(g),
+        Err(s) => return proc_macro::TokenStream::from(s),
+    };
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let name = &input.ident;
+
+    let expanded = quote! {
+        impl #impl_generics BorrowDecodable<'de> for #name #ty_generics #where_clause {
+            fn borrow_decode_from(
+                decoder: &mut ::byte_coding::Decoder<'de>,
+            ) -> Result<Self, ::byte_coding::DecodeError> {
+                return decoder.nested(|decoder| {
+                    #decoding_calls
+                });
             }
         }
     };
@@ -301,6 +705,19 @@ pub fn decodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 /// * `post_enc_func` - A string which contains the name of a function which should perform
 /// some operation on the encoded data before it is returned.
 /// e.g. `#[byte_coding(post_enc_func = "my_func")]`
+/// * `bound` - A string containing where clause predicates which replace the inferred bounds
+/// on the generated implementation. By default every type parameter used by a field that is
+/// encoded is required to implement `Encodable`. This value is used by both derive macros.
+/// e.g. `#[byte_coding(bound = "T: Encodable + Clone")]`
+/// * `enc_bound` - The same as `bound` but only used by the `Encodable` derive macro, if both
+/// are provided this value is used.
+/// e.g. `#[byte_coding(enc_bound = "T: Encodable + Clone")]`
+/// * `version` - Structs only. The current version of the struct's format, which is written
+/// as a `VarInt` before the fields.
+/// e.g. `#[byte_coding(version = 2)]`
+/// * `tagged` - Structs only. The number of fields is written as a `VarInt`, followed by each
+/// field as its tag and length, both `VarInt`s, and its value.
+/// e.g. `#[byte_coding(tagged)]`
 ///
 /// #### Struct Fields
 /// * `order_no` - An integer to indicate the order in which the field should be encoded.
@@ -308,13 +725,56 @@ pub fn decodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 /// have a value. However, no value can be repeated and any fields without order numbers
 /// will be processed from top to bottom after any fields with order numbers.
 /// e.g. `#[byte_coding(order_no = 0)]`
+/// * `tag` - The number which identifies a field of a tagged struct, every field which is
+/// encoded or decoded needs a tag and no tag can be repeated.
+/// e.g. `#[byte_coding(tag = 7)]`
 /// * `ignore` - Specify this option to ignore encoding this field.
 /// e.g. `#[byte_coding(ignore)]`
+/// * `skip_encode` - The same as `ignore`, but the field is still decoded. This is intended for
+/// fields which are only read, such as values filled in by the other side of a protocol. Since
+/// the field is read but not written, the data can only be encoded by a type which writes it.
+/// e.g. `#[byte_coding(skip_encode)]`
+/// * `skip_decode` - Only used by the `Decodable` derive macro, the field is still encoded.
+/// e.g. `#[byte_coding(skip_decode)]`
+/// * `varint` - Encode an integer field as a LEB128 variable length integer, see `VarInt`.
+/// e.g. `#[byte_coding(varint)]`
+/// * `zigzag` - Encode a signed integer field using zigzag encoding, see `ZigZag`. Only one of
+/// `varint` and `zigzag` can be specified. A generic field using either option needs a `bound`.
+/// e.g. `#[byte_coding(zigzag)]`
+/// * `since` - The first version of a versioned struct which contains this field, it can't be
+/// newer than the version of the struct.
+/// e.g. `#[byte_coding(since = 2)]`
+/// * `until` - The last version of a versioned struct which contains this field. A field whose
+/// `until` is older than the version of the struct is no longer encoded.
+/// e.g. `#[byte_coding(until = 1)]`
+/// * `present_if` - A string which contains a condition, the field is only encoded when it is
+/// true. `self.field` in the condition refers to a field of the value being encoded. For the
+/// data to be decodable, the condition should only use fields which are encoded before this one.
+/// e.g. `#[byte_coding(present_if = "self.flags & 0x4 != 0")]`
+/// * `count` - A string which contains the name of an integer field, which is written as the
+/// number of elements of this field instead of its own value. This field is written without its
+/// length prefix. If the length doesn't fit in the type of the length field
+/// `EncodeError::LengthFieldOverflow` is returned, which makes methods such as `encoded` panic.
+/// The rules for the length field are described by the `Decodable` derive macro.
+/// e.g. `#[byte_coding(count = "n_items")]`
+/// * `byte_len` - The same as `count`, but the length field is written as the number of bytes
+/// the elements are encoded in. With the `alloc` feature the elements are encoded once into a
+/// buffer before the length field is written, without it they are encoded twice.
+/// e.g. `#[byte_coding(byte_len = "payload_len")]`
+/// * `with` - A string which contains the path of a module whose `encode` function is used to
+/// encode this field instead of its `Encodable` implementation, such as for a foreign type. It
+/// can't be combined with `varint` or `zigzag`, and a generic field using it needs a `bound`.
+/// The signatures of the module's functions are described by the `Decodable` derive macro.
+/// e.g. `#[byte_coding(with = "my_module")]`
+/// * `encode_with` - A string which contains the path of the function used to encode this
+/// field, it is used instead of the `encode` function of the `with` module.
+/// e.g. `#[byte_coding(encode_with = "my_func")]`
 ///
 /// #### Enums
 /// * `encoding_type` - A string which indicates what type the enum variant values are,
-/// by default a 'u16' value is used. If a smaller or larger value is required it should
-/// be annotated here. Supported values: `["u8", "u16", "u32", "u64", "u128"]`.
+/// by default the enum tag encoding of the `Config` is used, which is a 'u16' value unless
+/// configured otherwise. If a specific smaller or larger type is required it should be
+/// annotated here. Supported values: `["u8", "u16", "u32", "u64", "u128"]`.
 /// e.g. `#[byte_coding(encoding_type = "u64")]`
 /// * `inferred_values` - A flag which when set indicates the byte_coding can infer values,
 /// by default these are numeric values starting at zero and increasing by 1 for each variant.
@@ -328,6 +788,11 @@ pub fn decodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 /// is not provided, instead this attribute must be set to inform the macro the values
 /// it should assign to each enum variant. e.g. `#[byte_coding(value = 1)]`
 ///
+/// #### Enum Variant Fields
+/// The fields of tuple and struct variants accept the same values as struct fields, except for
+/// `tag`, `since` and `until`. Each variant's fields are ordered and ignored independently.
+/// e.g. `#[byte_coding(order_no = 0)]`
+///
 /// # Examples
 /// ### Simple Examples
 /// Most direct serialisation and deserialisation should be able to be achieved without
@@ -488,6 +953,25 @@ pub fn decodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 /// assert_eq!(encoded, comparison_encoded);
 /// ```
 ///
+/// Generic types, lifetimes and const generics are supported. Each type parameter used by a
+/// field is required to implement `Encodable`, if a different bound is needed it can be provided
+/// with the `bound` or `enc_bound` key.
+///
+/// ```
+/// # use byte_coding::Encodable;
+///
+/// #[derive(Encodable)]
+/// struct Envelope<'a, T, const N: usize> {
+///     id: [u8; N],
+///     name: &'a str,
+///     body: T
+/// }
+///
+/// let encoded = Envelope { id: [1, 2], name: "a", body: 5u16 }.encoded();
+///
+/// assert_eq!(encoded, vec![1, 2, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 5, 0]);
+/// ```
+///
 /// Additionally if you want to perform some operation on the data or result before or after
 /// encoding, you can set a function the `pre_enc_func` key and the `post_enc_func` key.
 ///
@@ -498,7 +982,8 @@ pub fn decodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 /// ```
 ///
 /// The function specified as the `post_enc_func` must have the following type signature,
-/// but the function name can be chosen as you wish:
+/// but the function name can be chosen as you wish. The buffer contains only the bytes encoded
+/// for this value, which are written to the encoder's sink after the function returns:
 /// ```ignore
 /// fn post_enc_func(buffer: &mut Vec<u8>);
 /// ```
@@ -539,11 +1024,26 @@ pub fn encodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
     let input = parse_macro_input!(input as DeriveInput);
 
     let encoding_calls = encoding(&input);
-    let name = input.ident;
+    let generics = match ByteCodingAttr::from_data(&input).and_then(|(attr, _)| {
+        bounded_generics(
+            &input,
+            attr.enc_bound.as_ref().or(attr.bound.as_ref()),
+            quote! { Encodable },
+            ByteCodingStructFieldAttr::is_encoded,
+        )
+    }) {
+        Ok(g) => g,
+        Err(s) => return proc_macro::TokenStream::from(s),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let name = &input.ident;
 
     let expanded = quote! {
-        impl Encodable for #name {
-            fn encode_to_buf(&self, buf: &mut Vec<u8>) {
+        impl #impl_generics Encodable for #name #ty_generics #where_clause {
+            fn encode_to(
+                &self,
+                encoder: &mut ::byte_coding::Encoder<'_>,
+            ) -> Result<(), ::byte_coding::EncodeError> {
                 #encoding_calls
             }
         }
@@ -551,3 +1051,98 @@ pub fn encodable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 
     return proc_macro::TokenStream::from(expanded);
 }
+
+/// Generates an implementation of the FixedSize trait for a data type.
+///
+/// The size of a struct is the sum of the sizes of its fields which are encoded, each of
+/// which must implement `FixedSize`. Fields using `varint` or `zigzag` are rejected since
+/// their size depends on the value. The size of an enum is the size of its tag plus the size of
+/// its variants, which must all be the same. The tag size is taken from `encoding_type`, or is
+/// 2 bytes when it is not specified, matching the default `Config`. A type with a
+/// `post_enc_func` is rejected as the function can change the encoded size, as is a tagged
+/// struct since newer versions can write more fields, and a field with a custom codec, a
+/// `present_if` condition, a `count` or a `byte_len`.
+///
+/// The `Encodable` trait must also be implemented, usually by deriving it. Each type parameter
+/// used by a field is required to implement `FixedSize`.
+///
+/// # Available Attribute Values
+/// #### Structs and Enums
+/// * `size` - The expected encoded size in bytes. A type which doesn't match fails to compile,
+/// or for a generic type, fails when the size of an instantiation is evaluated.
+/// e.g. `#[byte_coding(size = 12)]`
+///
+/// The `ignore`, `skip_encode`, `varint`, `zigzag` and `encoding_type` values are read in the
+/// same way as the `Encodable` derive macro.
+///
+/// # Examples
+/// ```
+/// use byte_coding::{Encodable, FixedSize};
+///
+/// #[derive(Encodable, FixedSize)]
+/// #[byte_coding(size = 12)]
+/// struct Header {
+///     id: u32,
+///     length: u64,
+/// }
+///
+/// #[derive(Encodable, FixedSize)]
+/// #[byte_coding(encoding_type = "u8", inferred_values)]
+/// enum Command {
+///     Move(i16, i16),
+///     Wait(u32),
+/// }
+///
+/// assert_eq!(Command::SIZE, 5);
+///
+/// let mut buf = [0u8; Header::SIZE];
+/// Header { id: 1, length: 2 }.encode_to_slice(&mut buf).unwrap();
+/// ```
+///
+/// A mismatched size is rejected.
+/// ```compile_fail
+/// use byte_coding::{Encodable, FixedSize};
+///
+/// #[derive(Encodable, FixedSize)]
+/// #[byte_coding(size = 4)]
+/// struct Header {
+///     id: u32,
+///     length: u64,
+/// }
+/// ```
+#[proc_macro_derive(FixedSize, attributes(byte_coding))]
+pub fn fixed_size_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let size = fixed_size(&input);
+    let generics = match bounded_generics(
+        &input,
+        None,
+        quote! { FixedSize },
+        ByteCodingStructFieldAttr::is_encoded,
+    ) {
+        Ok(g) => g,
+        Err(s) => return proc_macro::TokenStream::from(s),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let name = &input.ident;
+
+    // The size of a generic type can only be checked once its parameters are known
+    let check = if input.generics.params.is_empty() {
+        quote! {
+            const _: usize = <#name as FixedSize>::SIZE;
+        }
+    } else {
+        quote! {}
+    };
+
+    let expanded = quote! {
+        impl #impl_generics FixedSize for #name #ty_generics #where_clause {
+            const SIZE: usize = #size;
+        }
+
+        #check
+    };
+
+    return proc_macro::TokenStream::from(expanded);
+}
@@ -1,11 +1,11 @@
 use std::collections::BTreeSet;
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::{quote_spanned, ToTokens};
 use syn::spanned::Spanned;
-use syn::{Expr, Variant};
+use syn::{Expr, Fields, Variant};
 
-use crate::byte_coding_attr::ByteCodingEnumVariantAttr;
+use crate::byte_coding_attr::{ByteCodingEnumVariantAttr, ByteCodingStructFieldAttr};
 
 pub fn u128_to_int_tok_stream<T: TryFrom<u128> + ToTokens>(
     value: u128,
@@ -75,3 +75,132 @@ pub fn parse_enum_variant_value(
 
     return Ok(value);
 }
+
+/// Checks that no two fields of a struct share an `order_no` or a `tag`, that tags are used on
+/// every field of a tagged struct and only in a tagged struct, and that the length fields used by
+/// `count` and `byte_len` are valid.
+pub fn check_struct_fields(fields: &Fields, tagged: bool) -> Result<(), TokenStream> {
+    let mut found_order_nos = BTreeSet::new();
+    let mut found_tags = BTreeSet::new();
+    let mut members = Vec::new();
+
+    for (i, f) in fields.iter().enumerate() {
+        let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
+        let member = match &f.ident {
+            Some(n) => n.to_string(),
+            None => i.to_string(),
+        };
+
+        members.push((member, field_attr.clone(), f.span()));
+
+        if let Some(order_no) = field_attr.order_no {
+            if !found_order_nos.insert(order_no) {
+                let error_message = format!("2 or more fields share the order_no: {}", order_no);
+
+                return Err(quote_spanned! {f.span()=>
+                    compile_error!(#error_message);
+                });
+            }
+        }
+
+        let Some(tag) = field_attr.tag else {
+            if tagged && (field_attr.is_encoded() || field_attr.is_decoded()) {
+                return Err(quote_spanned! {f.span()=>
+                    compile_error!("Every field of a tagged struct must have a tag.");
+                });
+            }
+
+            continue;
+        };
+
+        if !tagged {
+            return Err(quote_spanned! {f.span()=>
+                compile_error!("tag can only be used when the struct is tagged.");
+            });
+        }
+
+        if !found_tags.insert(tag) {
+            let error_message = format!("2 or more fields share the tag: {}", tag);
+
+            return Err(quote_spanned! {f.span()=>
+                compile_error!(#error_message);
+            });
+        }
+    }
+
+    return check_length_fields(members, tagged);
+}
+
+/// Checks that the field named by each `count` or `byte_len` exists, is encoded before the field
+/// whose length it stores and is always encoded and decoded. A length field can only store the
+/// length of one field.
+fn check_length_fields(
+    mut members: Vec<(String, ByteCodingStructFieldAttr, Span)>,
+    tagged: bool,
+) -> Result<(), TokenStream> {
+    members.sort_by(|(_, a, _), (_, b, _)| a.orderno_cmp(b));
+
+    let mut found_length_fields = BTreeSet::new();
+
+    for (i, (_, field_attr, span)) in members.iter().enumerate() {
+        let Some(length) = &field_attr.length else {
+            continue;
+        };
+
+        // The fields of a tagged struct can be read in any order
+        if tagged {
+            return Err(quote_spanned! {*span=>
+                compile_error!("count and byte_len can't be used in a tagged struct.");
+            });
+        }
+
+        let field = length.field();
+        let Some(pos) = members.iter().position(|(member, ..)| member == field) else {
+            let error_message = format!("The length field {} does not exist.", field);
+
+            return Err(quote_spanned! {*span=>
+                compile_error!(#error_message);
+            });
+        };
+
+        if pos >= i {
+            let error_message = format!(
+                "The length field {} must be encoded before this field.",
+                field
+            );
+
+            return Err(quote_spanned! {*span=>
+                compile_error!(#error_message);
+            });
+        }
+
+        let length_attr = &members[pos].1;
+
+        if !length_attr.is_encoded()
+            || !length_attr.is_decoded()
+            || length_attr.present_if.is_some()
+            || length_attr.since.is_some()
+            || length_attr.until.is_some()
+            || length_attr.length.is_some()
+        {
+            let error_message = format!(
+                "The length field {} must always be encoded and decoded as an integer.",
+                field
+            );
+
+            return Err(quote_spanned! {*span=>
+                compile_error!(#error_message);
+            });
+        }
+
+        if !found_length_fields.insert(field) {
+            let error_message = format!("2 or more fields use {} as their length.", field);
+
+            return Err(quote_spanned! {*span=>
+                compile_error!(#error_message);
+            });
+        }
+    }
+
+    return Ok(());
+}
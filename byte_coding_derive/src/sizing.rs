@@ -0,0 +1,185 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, Data, DataEnum, DeriveInput, Fields};
+
+use crate::byte_coding_attr::{ByteCodingAttr, ByteCodingStructFieldAttr, EnumEncodingType};
+
+/// Generates an expression which evaluates to the encoded size of the input type, including the
+/// assertion of the `size` attribute if one was provided.
+pub fn fixed_size(input: &DeriveInput) -> TokenStream {
+    let (toplevel_attr, first_enum_attr) = match ByteCodingAttr::from_data(input) {
+        Ok(v) => v,
+        Err(s) => return s,
+    };
+
+    // The hook can change the number of bytes written, so the size is unknown
+    if toplevel_attr.post_enc_func.is_some() {
+        return quote_spanned! {input.ident.span()=>
+            compile_error!("A type with a post_enc_func does not have a fixed size.")
+        };
+    }
+
+    // Fields written by a newer version of a tagged struct change its size
+    if toplevel_attr.tagged {
+        return quote_spanned! {input.ident.span()=>
+            compile_error!("A tagged struct does not have a fixed size.")
+        };
+    }
+
+    let version = toplevel_attr.version;
+
+    let size = match input.data {
+        Data::Enum(ref data) => {
+            if version.is_some() {
+                return quote_spanned! {input.ident.span()=>
+                    compile_error!("Only structs can have a version.")
+                };
+            }
+
+            match generate_enum_size(&toplevel_attr, data) {
+                Ok(s) => s,
+                Err(s) => return s,
+            }
+        }
+        Data::Struct(ref data) => {
+            if let Some(attr) = first_enum_attr {
+                return quote_spanned! {attr.span()=>
+                    compile_error!("Enum argument supplied to attribute on struct.")
+                };
+            }
+
+            let fields = match generate_fields_size(&data.fields, version) {
+                Ok(s) => s,
+                Err(s) => return s,
+            };
+
+            // The version header is a variable length integer, but the version is constant
+            match version {
+                Some(v) => {
+                    let header_size = varint_len(v);
+
+                    quote! { #header_size + #fields }
+                }
+                None => fields,
+            }
+        }
+        _ => panic!("Unsupported data type"),
+    };
+
+    let Some(expected) = toplevel_attr.size else {
+        return size;
+    };
+
+    let message = format!(
+        "The encoded size of {} is not {} bytes.",
+        input.ident, expected
+    );
+
+    return quote! {
+        {
+            let size = #size;
+            assert!(size == #expected, #message);
+
+            size
+        }
+    };
+}
+
+/// Returns the number of bytes in the LEB128 encoding of the value.
+fn varint_len(value: u32) -> usize {
+    return (32 - value.leading_zeros() as usize).div_ceil(7).max(1);
+}
+
+/// Sums the sizes of the fields which are encoded.
+fn generate_fields_size(fields: &Fields, version: Option<u32>) -> Result<TokenStream, TokenStream> {
+    let mut sizes = Vec::new();
+
+    for f in fields.iter() {
+        let field_attr = ByteCodingStructFieldAttr::parse_attributes(&f.attrs)?;
+        field_attr.check_version(version, f.span())?;
+
+        if !field_attr.is_encoded() || !field_attr.is_encoded_in(version) {
+            continue;
+        }
+
+        if field_attr.int_encoding.is_some() {
+            return Err(quote_spanned! {f.span()=>
+                compile_error!("A varint or zigzag field does not have a fixed size.")
+            });
+        }
+
+        if field_attr.present_if.is_some() {
+            return Err(quote_spanned! {f.span()=>
+                compile_error!("A field with a present_if condition does not have a fixed size.")
+            });
+        }
+
+        if field_attr.length.is_some() {
+            return Err(quote_spanned! {f.span()=>
+                compile_error!("A field with a count or byte_len does not have a fixed size.")
+            });
+        }
+
+        if field_attr.encode_with_fn().is_some() {
+            return Err(quote_spanned! {f.span()=>
+                compile_error!("A field with a custom codec does not have a fixed size.")
+            });
+        }
+
+        let ty = &f.ty;
+
+        sizes.push(quote_spanned! {f.span()=>
+            <#ty as FixedSize>::SIZE
+        });
+    }
+
+    return Ok(quote! {
+        0 #(+ #sizes)*
+    });
+}
+
+/// Every variant must have the same size, which is added to the size of the tag.
+fn generate_enum_size(
+    toplevel_attr: &ByteCodingAttr,
+    data: &DataEnum,
+) -> Result<TokenStream, TokenStream> {
+    // Without an encoding type the tag is written using the default u16 enum tag encoding
+    let tag_size: usize = match toplevel_attr
+        .enum_options
+        .as_ref()
+        .and_then(|opt| opt.encoding_type)
+    {
+        Some(EnumEncodingType::U8) => 1,
+        Some(EnumEncodingType::U16) | None => 2,
+        Some(EnumEncodingType::U32) => 4,
+        Some(EnumEncodingType::U64) => 8,
+        Some(EnumEncodingType::U128) => 16,
+    };
+
+    if data.variants.is_empty() {
+        return Ok(quote! { #tag_size });
+    }
+
+    let mut variant_sizes = Vec::new();
+
+    for variant in &data.variants {
+        variant_sizes.push(generate_fields_size(&variant.fields, None)?);
+    }
+
+    return Ok(quote! {
+        {
+            let sizes = [#(#variant_sizes),*];
+            let mut i = 1;
+
+            while i < sizes.len() {
+                assert!(
+                    sizes[i] == sizes[0],
+                    "Every variant of a FixedSize enum must have the same size."
+                );
+                i += 1;
+            }
+
+            #tag_size + sizes[0]
+        }
+    });
+}
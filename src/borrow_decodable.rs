@@ -0,0 +1,412 @@
+use core::cmp::Reverse;
+use core::marker::PhantomData;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping,
+};
+
+use crate::{Config, Decodable, DecodeError, DecodeLimits, Decoder, VarInt, ZigZag};
+
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+#[cfg(feature = "std")]
+use std::rc::Rc;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::borrow::Cow;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::boxed::Box;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::rc::Rc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::String;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+
+/// Provide methods to decode objects which borrow from the input buffer.
+///
+/// This trait is the zero-copy counterpart of [Decodable]. The lifetime `'de` is the lifetime of
+/// the input buffer, values such as `&'de str` and `&'de [u8]` point directly into that buffer
+/// instead of being copied. The encoded format is identical to the owned equivalents, so a
+/// `&str` can be decoded from the bytes of an encoded `String` and vice versa.
+///
+/// Implementations are provided for `&str`, `&[u8]`, `Cow<str>` and `Cow<[u8]>` (which always
+/// decode into the `Borrowed` variant), for `Option`, `Result` and tuples containing borrowed
+/// values, and for every owned type which implements [Decodable]. Owned collections such as
+/// `Vec<T>` decode their elements using [Decodable], so they cannot contain borrowed values.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{BorrowDecodable, Encodable};
+///
+/// let data = ("name", Some(vec![1u8, 2, 3])).encoded();
+/// let (name, bytes): (&str, Option<Vec<u8>>) = BorrowDecodable::borrow_decode(&data).unwrap();
+///
+/// assert_eq!(name, "name");
+/// assert_eq!(bytes, Some(vec![1, 2, 3]));
+/// # }
+/// ```
+///
+/// # Implementing
+/// The trait can be derived using the `BorrowDecodable` derive macro. When implementing the trait
+/// by hand, implement the `borrow_decode_from` method in the same way as the `decode_from` method
+/// of [Decodable].
+///
+/// ```
+/// use byte_coding::{BorrowDecodable, DecodeError, Decoder};
+///
+/// struct Example<'a> {
+///     f1: &'a str,
+///     f2: u16
+/// }
+///
+/// impl<'a, 'de: 'a> BorrowDecodable<'de> for Example<'a> {
+///     fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+///         let f1 = BorrowDecodable::borrow_decode_from(decoder)?;
+///         let f2 = BorrowDecodable::borrow_decode_from(decoder)?;
+///
+///         return Ok(Example { f1, f2 });
+///     }
+/// }
+/// ```
+pub trait BorrowDecodable<'de>
+where
+    Self: Sized,
+{
+    /// Decodes a slice of bytes into the object implemented on using the default
+    /// [DecodeLimits], borrowing from the slice where possible.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::BorrowDecodable;
+    ///
+    /// let src = vec![2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i'];
+    /// let decoded: &str = BorrowDecodable::borrow_decode(&src).unwrap();
+    /// assert_eq!("hi", decoded);
+    /// ```
+    fn borrow_decode(bytes: &'de [u8]) -> Result<Self, DecodeError> {
+        return Self::borrow_decode_with_limits(bytes, DecodeLimits::default());
+    }
+
+    /// Decodes a slice of bytes into the object implemented on using the specified limits,
+    /// borrowing from the slice where possible.
+    fn borrow_decode_with_limits(
+        bytes: &'de [u8],
+        limits: DecodeLimits,
+    ) -> Result<Self, DecodeError> {
+        return Self::borrow_decode_with_config(bytes, Config::default().with_limits(limits));
+    }
+
+    /// Decodes a slice of bytes into the object implemented on using the specified
+    /// configuration, borrowing from the slice where possible.
+    fn borrow_decode_with_config(bytes: &'de [u8], config: Config) -> Result<Self, DecodeError> {
+        return Self::borrow_decode_from(&mut Decoder::with_config(bytes, config));
+    }
+
+    /// Decodes the object implemented on from the decoder, borrowing from the decoder's input
+    /// where possible.
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError>;
+
+    /// Decodes a slice of bytes into the object implemented on using the default limits,
+    /// borrowing from the slice where possible. Returns a slice of the input buffer which
+    /// contains only unprocessed bytes.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::BorrowDecodable;
+    ///
+    /// let src = vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3];
+    /// let (decoded, buffer) = <&[u8]>::borrow_decode_from_buf(&src).unwrap();
+    /// assert_eq!(decoded, &[1, 2]);
+    /// assert_eq!(buffer, &[3]);
+    /// ```
+    fn borrow_decode_from_buf(buffer: &'de [u8]) -> Result<(Self, &'de [u8]), DecodeError> {
+        let mut decoder = Decoder::new(buffer);
+        let res = Self::borrow_decode_from(&mut decoder)?;
+
+        return Ok((res, decoder.remaining()));
+    }
+}
+
+impl<'a, 'de: 'a> BorrowDecodable<'de> for &'a [u8] {
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        let len = decoder.read_length_prefix()?;
+
+        return decoder.read_bytes(len);
+    }
+}
+
+impl<'a, 'de: 'a> BorrowDecodable<'de> for &'a str {
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        let bytes = <&[u8]>::borrow_decode_from(decoder)?;
+
+        return core::str::from_utf8(bytes).map_err(|e| DecodeError::InvalidUtf8 {
+            valid_up_to: e.valid_up_to(),
+        });
+    }
+}
+
+/// Always decodes into the `Borrowed` variant.
+#[cfg(feature = "alloc")]
+impl<'a, 'de: 'a> BorrowDecodable<'de> for Cow<'a, str> {
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return <&str>::borrow_decode_from(decoder).map(Cow::Borrowed);
+    }
+}
+
+/// Always decodes into the `Borrowed` variant.
+#[cfg(feature = "alloc")]
+impl<'a, 'de: 'a> BorrowDecodable<'de> for Cow<'a, [u8]> {
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return <&[u8]>::borrow_decode_from(decoder).map(Cow::Borrowed);
+    }
+}
+
+impl<'de, T: BorrowDecodable<'de>> BorrowDecodable<'de> for Option<T> {
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        let present = u8::decode_from(decoder)?;
+
+        return match present {
+            0 => Ok(None),
+            1 => decoder.nested(|decoder| T::borrow_decode_from(decoder).map(Some)),
+            _ => Err(DecodeError::UnknownEnumTag {
+                tag: present as u128,
+            }),
+        };
+    }
+}
+
+impl<'de, T: BorrowDecodable<'de>, E: BorrowDecodable<'de>> BorrowDecodable<'de> for Result<T, E> {
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        let tag = u8::decode_from(decoder)?;
+
+        return match tag {
+            0 => decoder.nested(|decoder| T::borrow_decode_from(decoder).map(Ok)),
+            1 => decoder.nested(|decoder| E::borrow_decode_from(decoder).map(Err)),
+            _ => Err(DecodeError::UnknownEnumTag { tag: tag as u128 }),
+        };
+    }
+}
+
+macro_rules! impl_borrow_decodable_tuple {
+    ($($name:ident $var:ident),+) => {
+        impl<'de, $($name: BorrowDecodable<'de>),+> BorrowDecodable<'de> for ($($name,)+) {
+            fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+                $(let $var = $name::borrow_decode_from(decoder)?;)+
+
+                return Ok(($($var,)+));
+            }
+        }
+    };
+}
+
+impl_borrow_decodable_tuple!(A a);
+impl_borrow_decodable_tuple!(A a, B b);
+impl_borrow_decodable_tuple!(A a, B b, C c);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o);
+impl_borrow_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o, P p);
+
+/// Owned types are decoded using their [Decodable] implementation.
+macro_rules! impl_borrow_decodable_owned {
+    ($($ty:ty),+) => {
+        $(
+            impl<'de> BorrowDecodable<'de> for $ty {
+                fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+                    return Self::decode_from(decoder);
+                }
+            }
+        )+
+    };
+}
+
+impl_borrow_decodable_owned!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    usize,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    (),
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroUsize,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128,
+    NonZeroIsize
+);
+
+/// Generic owned types are decoded using their [Decodable] implementation.
+macro_rules! impl_borrow_decodable_owned_generic {
+    ($($ty:ident),+) => {
+        $(
+            impl<'de, T> BorrowDecodable<'de> for $ty<T>
+            where
+                $ty<T>: Decodable,
+            {
+                fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+                    return Self::decode_from(decoder);
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_borrow_decodable_owned!(String);
+
+impl_borrow_decodable_owned_generic!(PhantomData, Wrapping, Saturating, Reverse, VarInt, ZigZag);
+
+#[cfg(feature = "alloc")]
+impl_borrow_decodable_owned_generic!(Box, Rc, Arc, Vec, VecDeque, LinkedList, BinaryHeap, BTreeSet);
+
+impl<'de, T, const N: usize> BorrowDecodable<'de> for [T; N]
+where
+    [T; N]: Decodable,
+{
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return Self::decode_from(decoder);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, K, V> BorrowDecodable<'de> for BTreeMap<K, V>
+where
+    BTreeMap<K, V>: Decodable,
+{
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return Self::decode_from(decoder);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, K, V, S> BorrowDecodable<'de> for HashMap<K, V, S>
+where
+    HashMap<K, V, S>: Decodable,
+{
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return Self::decode_from(decoder);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, T, S> BorrowDecodable<'de> for HashSet<T, S>
+where
+    HashSet<T, S>: Decodable,
+{
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return Self::decode_from(decoder);
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::Encodable;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    #[test]
+    fn test_str() {
+        let encoded = "test".to_string().encoded();
+        let (res, buffer) = <&str>::borrow_decode_from_buf(&encoded).unwrap();
+
+        assert_eq!(res, "test");
+        assert!(buffer.is_empty());
+        assert_eq!(res.as_ptr(), encoded[8..].as_ptr());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let encoded = vec![1u8, 2, 3].encoded();
+        let res: &[u8] = BorrowDecodable::borrow_decode(&encoded).unwrap();
+
+        assert_eq!(res, &[1, 2, 3]);
+        assert_eq!(res.as_ptr(), encoded[8..].as_ptr());
+    }
+
+    #[test]
+    fn test_cow() {
+        let encoded = "test".encoded();
+        let res: Cow<str> = BorrowDecodable::borrow_decode(&encoded).unwrap();
+
+        assert!(matches!(res, Cow::Borrowed("test")));
+
+        let res: Cow<[u8]> = BorrowDecodable::borrow_decode(&encoded).unwrap();
+
+        assert!(matches!(res, Cow::Borrowed(b"test")));
+    }
+
+    #[test]
+    fn test_nested() {
+        let b: (Option<&str>, Result<u8, &str>, Vec<u16>) = (Some("a"), Err("b"), vec![1, 2]);
+        let encoded = b.encoded();
+        let res: (Option<&str>, Result<u8, &str>, Vec<u16>) =
+            BorrowDecodable::borrow_decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let encoded = "test".encoded();
+
+        assert_eq!(
+            <&str>::borrow_decode(&encoded[..10]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 4,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let encoded = [1, 0, 0, 0, 0, 0, 0, 0, 0xff];
+
+        assert_eq!(
+            <&str>::borrow_decode(&encoded),
+            Err(DecodeError::InvalidUtf8 { valid_up_to: 0 })
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use crate::{Decodable, Encodable};
+use crate::{
+    BorrowDecodable, Config, Decodable, DecodeError, DecodeLimits, Decoder, Encodable, EncodeError,
+    Encoder,
+};
 
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::vec::Vec;
 
 /// This object is a simple frontend that is useful when encoding or decoding multiple
@@ -24,6 +27,7 @@ use alloc::vec::Vec;
 pub struct Coder {
     buffer: Vec<u8>,
     decode_index: usize,
+    config: Config,
 }
 
 impl Coder {
@@ -37,6 +41,7 @@ impl Coder {
         return Self {
             buffer,
             decode_index: 0,
+            config: Config::default(),
         };
     }
 
@@ -45,29 +50,131 @@ impl Coder {
         self.decode_index = 0;
     }
 
+    /// Sets the limits used when decoding each object, the default limits are used otherwise.
+    pub fn set_decode_limits(&mut self, limits: DecodeLimits) {
+        self.config = self.config.with_limits(limits);
+    }
+
+    /// Sets the configuration used when encoding and decoding each object, the default
+    /// configuration is used otherwise. Objects already in the buffer are not re-encoded.
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// Returns the configuration used when encoding and decoding each object.
+    pub fn config(&self) -> &Config {
+        return &self.config;
+    }
+
+    /// Returns the offset of the object `decode_next_object` decodes next.
+    pub fn decode_index(&self) -> usize {
+        return self.decode_index;
+    }
+
     /// Returns a reference to the underlying buffer
     pub fn buffer(&self) -> &Vec<u8> {
         return &self.buffer;
     }
 
     /// Encode an object to the internal buffer.
+    ///
+    /// # Panics
+    /// Panics if the object cannot be encoded using the coder's configuration. The
+    /// implementations provided by this crate never fail when using the default configuration,
+    /// `try_encode` returns the error instead.
     pub fn encode<T: Encodable>(&mut self, object: &T) {
-        object.encode_to_buf(&mut self.buffer);
+        if let Err(e) = self.try_encode(object) {
+            panic!("encoding into a Coder failed: {}", e);
+        }
     }
 
     /// Encode an object to the internal buffer.
+    ///
+    /// # Panics
+    /// Panics in the same cases as `encode`, `try_encode_object` returns the error instead.
     pub fn encode_object<T: Encodable>(&mut self, object: T) {
-        object.encode_to_buf(&mut self.buffer);
+        self.encode(&object);
+    }
+
+    /// Encode an object to the internal buffer using the coder's configuration. If the encode
+    /// fails the error is returned and the buffer is left unchanged.
+    ///
+    /// ## Example
+    /// ```
+    /// use byte_coding::{Coder, Config, EncodeError, IntEncoding};
+    ///
+    /// let mut coder = Coder::new();
+    /// coder.set_config(Config::new().with_length_encoding(IntEncoding::U8));
+    ///
+    /// assert_eq!(
+    ///     coder.try_encode(&vec![0u8; 256]),
+    ///     Err(EncodeError::IntegerTooLarge { value: 256, max: 255 })
+    /// );
+    /// assert!(coder.buffer().is_empty());
+    /// ```
+    pub fn try_encode<T: Encodable>(&mut self, object: &T) -> Result<(), EncodeError> {
+        let len = self.buffer.len();
+        let res = object.encode_to(&mut Encoder::with_config(&mut self.buffer, self.config));
+
+        if res.is_err() {
+            self.buffer.truncate(len);
+        }
+
+        return res;
+    }
+
+    /// Encode an object to the internal buffer using the coder's configuration. If the encode
+    /// fails the error is returned and the buffer is left unchanged.
+    pub fn try_encode_object<T: Encodable>(&mut self, object: T) -> Result<(), EncodeError> {
+        return self.try_encode(&object);
     }
 
     /// Attempts to decode an object from the buffer continuing from the previously decoded object.
-    pub fn decode_next_object<T: Decodable>(&mut self) -> Option<T> {
-        let (res, b) = T::decode_from_buf(&self.buffer[self.decode_index..])?;
+    /// If the decode fails the decode index is left unchanged.
+    pub fn decode_next_object<T: Decodable>(&mut self) -> Result<T, DecodeError> {
+        let mut decoder = Decoder::with_config(&self.buffer[self.decode_index..], self.config);
+        let res = T::decode_from(&mut decoder)?;
+
+        self.decode_index = self.buffer.len() - decoder.remaining().len();
+
+        return Ok(res);
+    }
 
-        self.decode_index =
-            unsafe { b.as_ptr_range().start.offset_from(self.buffer.as_ptr()) } as usize;
+    /// Attempts to decode an object which borrows from the internal buffer, starting at `offset`.
+    /// The object is returned with the offset of the object after it, which can be passed to the
+    /// next call. The decode index used by `decode_next_object` is not changed.
+    ///
+    /// Multiple borrowed objects can be held at once, the buffer cannot be modified until they
+    /// have all been dropped.
+    ///
+    /// ## Example
+    /// ```
+    /// use byte_coding::Coder;
+    ///
+    /// let mut coder = Coder::new();
+    ///
+    /// coder.encode_object("first");
+    /// coder.encode_object("second");
+    ///
+    /// let (first, offset): (&str, _) = coder.decode_borrowed_at(0).unwrap();
+    /// let (second, _): (&str, _) = coder.decode_borrowed_at(offset).unwrap();
+    /// assert_eq!((first, second), ("first", "second"));
+    /// ```
+    pub fn decode_borrowed_at<'a, T: BorrowDecodable<'a>>(
+        &'a self,
+        offset: usize,
+    ) -> Result<(T, usize), DecodeError> {
+        let buffer = self
+            .buffer
+            .get(offset..)
+            .ok_or(DecodeError::UnexpectedEnd {
+                needed: offset,
+                available: self.buffer.len(),
+            })?;
+        let mut decoder = Decoder::with_config(buffer, self.config);
+        let res = T::borrow_decode_from(&mut decoder)?;
 
-        return Some(res);
+        return Ok((res, self.buffer.len() - decoder.remaining().len()));
     }
 }
 
@@ -76,6 +183,7 @@ impl Default for Coder {
         return Self {
             buffer: Vec::new(),
             decode_index: 0,
+            config: Config::default(),
         };
     }
 }
@@ -95,6 +203,7 @@ impl From<Vec<u8>> for Coder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::IntEncoding;
 
     #[cfg(not(feature = "std"))]
     use alloc::string::{String, ToString};
@@ -151,4 +260,73 @@ mod tests {
         let d: String = coder.decode_next_object().unwrap();
         assert_eq!(d, "test3".to_string());
     }
+
+    #[test]
+    fn test_decode_borrowed_at() {
+        let mut coder = Coder::new();
+
+        coder.encode_object("test");
+        coder.encode_object(0xffffu16);
+        coder.encode_object(&[1u8, 2, 3][..]);
+
+        let (f, offset): (&str, _) = coder.decode_borrowed_at(0).unwrap();
+        let (e, offset): (u16, _) = coder.decode_borrowed_at(offset).unwrap();
+        let (d, offset): (&[u8], _) = coder.decode_borrowed_at(offset).unwrap();
+
+        assert_eq!(f, "test");
+        assert_eq!(e, 0xffff);
+        assert_eq!(d, &[1, 2, 3]);
+        assert_eq!(coder.decode_index(), 0);
+
+        assert!(coder.decode_borrowed_at::<u8>(offset).is_err());
+        assert!(coder.decode_borrowed_at::<u8>(offset + 1).is_err());
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Coder>();
+    }
+
+    #[test]
+    fn test_set_config() {
+        let mut coder = Coder::new();
+
+        coder.set_config(Config::new().with_length_encoding(IntEncoding::U8));
+        coder.encode_object("ab");
+        coder.encode_object(1u16);
+
+        assert_eq!(coder.buffer(), &vec![2, b'a', b'b', 1, 0]);
+
+        let (s, offset): (&str, _) = coder.decode_borrowed_at(0).unwrap();
+        let (v, _): (u16, _) = coder.decode_borrowed_at(offset).unwrap();
+
+        assert_eq!((s, v), ("ab", 1));
+    }
+
+    #[test]
+    fn test_encode_error() {
+        let mut coder = Coder::new();
+
+        coder.set_config(Config::new().with_length_encoding(IntEncoding::U8));
+        coder.encode_object(1u8);
+
+        assert_eq!(
+            coder.try_encode_object((2u8, vec![0u8; 256])),
+            Err(EncodeError::IntegerTooLarge {
+                value: 256,
+                max: 255
+            })
+        );
+        assert_eq!(coder.buffer(), &vec![1]);
+    }
+    #[test]
+    #[should_panic]
+    fn test_encode_panics() {
+        let mut coder = Coder::new();
+
+        coder.set_config(Config::new().with_length_encoding(IntEncoding::U8));
+        coder.encode_object(vec![0u8; 256]);
+    }
 }
@@ -0,0 +1,125 @@
+use crate::DecodeLimits;
+
+/// The byte order used for integers and floats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// The least significant byte is written first. This is the default.
+    Little,
+    /// The most significant byte is written first.
+    Big,
+}
+
+/// How an integer such as a length prefix or enum tag is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IntEncoding {
+    /// A single byte.
+    U8,
+    /// Two bytes in the configured byte order.
+    U16,
+    /// Four bytes in the configured byte order.
+    U32,
+    /// Eight bytes in the configured byte order.
+    U64,
+    /// An unsigned LEB128 variable length integer, between 1 and 10 bytes. Each byte stores 7
+    /// bits of the value, least significant first, with the high bit set on every byte except
+    /// the last.
+    VarInt,
+}
+
+/// Controls the format used to encode and decode values.
+///
+/// The default configuration matches the format used by previous versions of this crate:
+/// little endian integers, 8 byte length prefixes and 2 byte enum tags. Data must be decoded
+/// using the same configuration it was encoded with.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Config, Decodable, Encodable, Endian, IntEncoding};
+///
+/// let config = Config::new()
+///     .with_endian(Endian::Big)
+///     .with_length_encoding(IntEncoding::U16);
+///
+/// let encoded = vec![0x0102u16, 0x0304].encoded_with_config(config).unwrap();
+/// assert_eq!(encoded, vec![0, 2, 1, 2, 3, 4]);
+///
+/// let decoded = Vec::<u16>::decode_with_config(&encoded, config).unwrap();
+/// assert_eq!(decoded, vec![0x0102, 0x0304]);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Config {
+    endian: Endian,
+    length_encoding: IntEncoding,
+    enum_tag_encoding: IntEncoding,
+    limits: DecodeLimits,
+}
+
+impl Config {
+    /// Creates the default configuration.
+    pub const fn new() -> Self {
+        return Self {
+            endian: Endian::Little,
+            length_encoding: IntEncoding::U64,
+            enum_tag_encoding: IntEncoding::U16,
+            limits: DecodeLimits::new(),
+        };
+    }
+
+    /// Sets the byte order used for integers, floats, length prefixes and enum tags.
+    pub const fn with_endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+
+        return self;
+    }
+
+    /// Sets how the length prefix of strings and collections is written.
+    pub const fn with_length_encoding(mut self, length_encoding: IntEncoding) -> Self {
+        self.length_encoding = length_encoding;
+
+        return self;
+    }
+
+    /// Sets how the tag of a derived enum is written, when the enum does not specify an
+    /// `encoding_type`.
+    pub const fn with_enum_tag_encoding(mut self, enum_tag_encoding: IntEncoding) -> Self {
+        self.enum_tag_encoding = enum_tag_encoding;
+
+        return self;
+    }
+
+    /// Sets the limits used when decoding.
+    pub const fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+
+        return self;
+    }
+
+    /// Returns the byte order.
+    pub const fn endian(&self) -> Endian {
+        return self.endian;
+    }
+
+    /// Returns how the length prefix of strings and collections is written.
+    pub const fn length_encoding(&self) -> IntEncoding {
+        return self.length_encoding;
+    }
+
+    /// Returns how the tag of a derived enum is written.
+    pub const fn enum_tag_encoding(&self) -> IntEncoding {
+        return self.enum_tag_encoding;
+    }
+
+    /// Returns the limits used when decoding.
+    pub const fn limits(&self) -> &DecodeLimits {
+        return &self.limits;
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
@@ -6,29 +6,62 @@ use core::mem::ManuallyDrop;
 #[cfg(all(feature = "std", feature = "bool_arr_optimization"))]
 use std::any::{Any, TypeId};
 #[cfg(feature = "std")]
-use std::collections::HashMap;
+use std::borrow::Cow;
 #[cfg(feature = "std")]
-use std::hash::Hash;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
 #[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+#[cfg(all(feature = "std", feature = "bool_arr_optimization"))]
 use std::mem::ManuallyDrop;
-
-#[cfg(not(feature = "std"))]
+#[cfg(feature = "std")]
+use std::rc::Rc;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, RwLock};
+
+use core::cell::{Cell, RefCell};
+use core::cmp::Reverse;
+use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use core::mem::size_of;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping,
+};
+
+use crate::{Config, DecodeError, DecodeLimits, Decoder, Endian};
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::borrow::{Cow, ToOwned};
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::boxed::Box;
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::rc::Rc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::string::String;
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::vec::Vec;
 
 /// Provide methods to decode objects from a vector of bytes.
 ///
 /// # Usage
-/// The `decoded` method should be used when decoding a slice into an object, alternatively if decoding
-/// multiple objects at a time, the `decode_from_buf` method can be used. Implementations have been
+/// The `decode` method should be used when decoding a slice into an object, alternatively if decoding
+/// multiple objects at a time, the `try_decode_from_buf` method can be used. Implementations have been
 /// provided for some common types but for custom types or other types the trait can be implemented
 /// using the below instructions.
 ///
+/// Decoding is limited by a [DecodeLimits] value, the `decode` method uses the default limits
+/// which are suitable for untrusted input. Different limits can be used with the
+/// `decode_with_limits` method. Data encoded using a non-default [Config] must be decoded with
+/// the same configuration using the `decode_with_config` method.
+///
 /// ## Example
 /// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
 /// use byte_coding::Decodable;
 ///
 /// let data = vec![1, 4, 0, 0, 0, 0, 0, 0, 0, b't', b'e', b's', b't'];
@@ -36,19 +69,23 @@ use alloc::vec::Vec;
 ///
 /// // Call the encoded method to convert to a buffer
 /// assert_eq!(Some("test".to_string()), decoded);
+/// # }
 /// ```
 ///
 /// # Implementing
-/// When implementing this trait, you must implement the `decode_from_buf` method. This method should
-/// consume as much of the input buffer as necessary to decode the object and return any left over in
-/// the tuple as a result with the decoded object. This is to allow the chaining of multiple decodes.
-/// If an error occurs or the input data is poorly formatted, you should return None to indicate to the
-/// caller that an error occurred.
+/// When implementing this trait, you should implement the `decode_from` method. This method
+/// should read as much of the input as necessary from the [Decoder] to decode the object, the
+/// remaining input is left in the decoder to allow the chaining of multiple decodes. If an error
+/// occurs or the input data is poorly formatted, you should return a [DecodeError] describing
+/// the problem. Implementations which allocate based on a length read from the input should use
+/// [Decoder::read_length] so that the length is checked against the decoder's limits.
 ///
 /// ## Example
 /// The below example is an implementation of the trait for an example struct.
 /// ```
-/// use byte_coding::Decodable;
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Decodable, DecodeError, Decoder};
 ///
 /// struct Example {
 ///     f1: String,
@@ -56,18 +93,21 @@ use alloc::vec::Vec;
 /// }
 ///
 /// impl Decodable for Example {
-///     fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-///         let (f1, buffer) = Decodable::decode_from_buf(buffer)?;
-///         let (f2, buffer) = Decodable::decode_from_buf(buffer)?;
+///     fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+///         let f1 = Decodable::decode_from(decoder)?;
+///         let f2 = Decodable::decode_from(decoder)?;
 ///
-///         return Some((Example {f1, f2}, buffer));
+///         return Ok(Example {f1, f2});
 ///     }
 /// }
+/// # }
 /// ```
 ///
 /// You can then encode the object like any other type
 /// ```
-/// # use byte_coding::Decodable;
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// # use byte_coding::{Decodable, DecodeError, Decoder};
 /// # #[derive(Debug, PartialEq)]
 /// # struct Example {
 /// #     f1: String,
@@ -75,11 +115,11 @@ use alloc::vec::Vec;
 /// # }
 /// #
 /// # impl Decodable for Example {
-/// #     fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-/// #         let (f1, buffer) = Decodable::decode_from_buf(buffer)?;
-/// #         let (f2, buffer) = Decodable::decode_from_buf(buffer)?;
+/// #     fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+/// #         let f1 = Decodable::decode_from(decoder)?;
+/// #         let f2 = Decodable::decode_from(decoder)?;
 /// #
-/// #         return Some((Example {f1, f2}, buffer));
+/// #         return Ok(Example {f1, f2});
 /// #     }
 /// # }
 /// #
@@ -90,13 +130,22 @@ use alloc::vec::Vec;
 ///     f1: "example".to_string(),
 ///     f2: 65535
 /// }, decoded);
+/// # }
 /// ```
+///
+/// ## Migrating from `decode_from_buf`
+/// Older versions of this trait required the `decode_from_buf` method, which returns an `Option`.
+/// `decode_from` is now the required method, an implementation of `decode_from_buf` can be
+/// converted by reading from `decoder.try_remaining()` and passing the unprocessed bytes to
+/// `decoder.set_remaining`. `decode_from_buf` is still provided, on top of `decode_from`, so
+/// existing callers continue to compile.
 pub trait Decodable
 where
     Self: Sized,
 {
-    /// Decodes a slice of bytes into the object implemented on. If the decode fails, a `None`
-    /// value is returned instead.
+    /// Decodes a slice of bytes into the object implemented on using the default
+    /// [DecodeLimits]. If the decode fails, a [DecodeError] describing the failure is returned
+    /// instead.
     ///
     /// ### Example
     /// ```
@@ -106,231 +155,288 @@ where
     /// let decoded = u16::decode(&src).unwrap();
     /// assert_eq!(65535, decoded);
     /// ```
-    fn decode(bytes: &[u8]) -> Option<Self> {
-        return Self::decode_from_buf(bytes).map(|(v, _)| v);
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        return Self::decode_with_limits(bytes, DecodeLimits::default());
     }
 
-    /// Decodes a slice of bytes into the object implemented on, returns a slice of the input
-    /// buffer which contains only unprocessed bytes. If the decode fails, a `None` value is
-    /// returned instead.
+    /// Decodes a slice of bytes into the object implemented on using the specified limits. If
+    /// the decode fails, a [DecodeError] describing the failure is returned instead.
     ///
     /// ### Example
     /// ```
-    /// use byte_coding::Decodable;
+    /// use byte_coding::{Decodable, DecodeError, DecodeLimits};
     ///
-    /// let src = vec![255, 255, 0, 0, 0];
-    /// let (decoded, buffer) = u16::decode_from_buf(&src).unwrap();
-    /// assert_eq!(65535, decoded);
-    /// assert_eq!(buffer, &[0, 0, 0]);
+    /// let src = vec![1, 1, 1, 0];
+    /// let limits = DecodeLimits::default().with_max_depth(2);
+    /// let res = Option::<Option<Option<u8>>>::decode_with_limits(&src, limits);
+    /// assert_eq!(res, Err(DecodeError::DepthLimit { max: 2 }));
     /// ```
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])>;
-}
+    fn decode_with_limits(bytes: &[u8], limits: DecodeLimits) -> Result<Self, DecodeError> {
+        return Self::decode_with_config(bytes, Config::default().with_limits(limits));
+    }
 
-impl Decodable for u8 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 1 {
-            return None;
-        }
+    /// Decodes a slice of bytes into the object implemented on using the specified
+    /// configuration. If the decode fails, a [DecodeError] describing the failure is returned
+    /// instead.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::{Config, Decodable, Endian};
+    ///
+    /// let src = vec![1, 2];
+    /// let config = Config::new().with_endian(Endian::Big);
+    /// assert_eq!(u16::decode_with_config(&src, config), Ok(258));
+    /// ```
+    fn decode_with_config(bytes: &[u8], config: Config) -> Result<Self, DecodeError> {
+        return Self::decode_from(&mut Decoder::with_config(bytes, config));
+    }
 
-        return Some((Self::from_le_bytes([buffer[0]]), &buffer[1..]));
+    /// Decodes the object implemented on from the reader using the default [DecodeLimits]. Only
+    /// the bytes required by the object are read, so further objects can be decoded from the
+    /// same reader. If the decode fails, a [DecodeError] describing the failure is returned
+    /// instead.
+    ///
+    /// Each value reads its bytes separately, so a `std::io::BufReader` should be used when
+    /// reading from a file or socket.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::Decodable;
+    /// use std::io::Cursor;
+    ///
+    /// let mut reader = Cursor::new(vec![255, 255, 1, 0, 0, 0, 0, 0, 0, 0, b'a']);
+    /// assert_eq!(u16::decode_from_reader(&mut reader), Ok(65535));
+    /// assert_eq!(String::decode_from_reader(&mut reader), Ok("a".to_string()));
+    /// assert!(u8::decode_from_reader(&mut reader).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    fn decode_from_reader<R: std::io::Read>(reader: R) -> Result<Self, DecodeError> {
+        return Self::decode_from_reader_with_config(reader, Config::default());
     }
-}
 
-impl Decodable for u16 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 2 {
-            return None;
-        }
+    /// Decodes the object implemented on from the reader using the specified configuration.
+    /// Only the bytes required by the object are read. If the decode fails, a [DecodeError]
+    /// describing the failure is returned instead.
+    #[cfg(feature = "std")]
+    fn decode_from_reader_with_config<R: std::io::Read>(
+        mut reader: R,
+        config: Config,
+    ) -> Result<Self, DecodeError> {
+        return Self::decode_from(&mut Decoder::from_reader_with_config(&mut reader, config));
+    }
 
-        let mut bytes_array = [0u8; 2];
+    /// Decodes the object implemented on from the decoder, leaving any unprocessed bytes in the
+    /// decoder. If the decode fails, a [DecodeError] describing the failure is returned instead.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::{Decodable, Decoder};
+    ///
+    /// let mut decoder = Decoder::new(&[255, 255, 0, 0, 0]);
+    /// assert_eq!(u16::decode_from(&mut decoder), Ok(65535));
+    /// assert_eq!(decoder.remaining(), &[0, 0, 0]);
+    /// ```
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError>;
 
-        bytes_array.copy_from_slice(&buffer[0..2]);
+    /// Decodes a slice of bytes into the object implemented on using the default limits, returns
+    /// a slice of the input buffer which contains only unprocessed bytes. If the decode fails, a
+    /// [DecodeError] describing the failure is returned instead.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::Decodable;
+    ///
+    /// let src = vec![255, 255, 0, 0, 0];
+    /// let (decoded, buffer) = u16::try_decode_from_buf(&src).unwrap();
+    /// assert_eq!(65535, decoded);
+    /// assert_eq!(buffer, &[0, 0, 0]);
+    /// ```
+    fn try_decode_from_buf(buffer: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        let mut decoder = Decoder::new(buffer);
+        let res = Self::decode_from(&mut decoder)?;
 
-        return Some((Self::from_le_bytes(bytes_array), &buffer[2..]));
+        return Ok((res, decoder.remaining()));
     }
-}
 
-impl Decodable for u32 {
+    /// Decodes a slice of bytes into the object implemented on, returns a slice of the input
+    /// buffer which contains only unprocessed bytes. If the decode fails, a `None` value is
+    /// returned instead.
+    ///
+    /// This method only exists so that callers written against older versions of this trait
+    /// continue to compile, use `try_decode_from_buf` or `decode_from` instead.
+    #[deprecated(note = "use `decode_from`, which reports why decoding failed")]
     fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 4 {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; 4];
-
-        bytes_array.copy_from_slice(&buffer[0..4]);
-
-        return Some((Self::from_le_bytes(bytes_array), &buffer[4..]));
+        return Self::try_decode_from_buf(buffer).ok();
     }
 }
 
-impl Decodable for u64 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 8 {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; 8];
+macro_rules! impl_decodable_int {
+    ($($ty:ident),+) => {
+        $(
+            impl Decodable for $ty {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    let bytes = decoder.read_array()?;
+
+                    return Ok(match decoder.config().endian() {
+                        Endian::Little => Self::from_le_bytes(bytes),
+                        Endian::Big => Self::from_be_bytes(bytes),
+                    });
+                }
+            }
+        )+
+    };
+}
 
-        bytes_array.copy_from_slice(&buffer[0..8]);
+impl_decodable_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
 
-        return Some((Self::from_le_bytes(bytes_array), &buffer[8..]));
+/// The bit pattern is restored exactly, see the [Encodable](crate::Encodable) implementation.
+impl Decodable for f32 {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return Ok(Self::from_bits(u32::decode_from(decoder)?));
     }
 }
 
-impl Decodable for u128 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 16 {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; 16];
-
-        bytes_array.copy_from_slice(&buffer[0..16]);
-
-        return Some((Self::from_le_bytes(bytes_array), &buffer[16..]));
+/// The bit pattern is restored exactly, see the [Encodable](crate::Encodable) implementation.
+impl Decodable for f64 {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return Ok(Self::from_bits(u64::decode_from(decoder)?));
     }
 }
 
-impl Decodable for i8 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        const BYTES: usize = 1;
-
-        if buffer.len() < BYTES {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; BYTES];
-
-        bytes_array.copy_from_slice(&buffer[0..BYTES]);
+/// Encoded using 8 bytes on every platform. Values which do not fit in a `usize`, which is only
+/// possible on platforms with a pointer width smaller than 64 bits, are rejected.
+impl Decodable for usize {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let v = u64::decode_from(decoder)?;
 
-        return Some((Self::from_le_bytes(bytes_array), &buffer[BYTES..]));
+        return Self::try_from(v).map_err(|_| DecodeError::UnsignedIntegerOverflow { value: v });
     }
 }
 
-impl Decodable for i16 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        const BYTES: usize = 2;
-
-        if buffer.len() < BYTES {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; BYTES];
-
-        bytes_array.copy_from_slice(&buffer[0..BYTES]);
+/// Encoded using 8 bytes on every platform. Values which do not fit in an `isize`, which is only
+/// possible on platforms with a pointer width smaller than 64 bits, are rejected.
+impl Decodable for isize {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let v = i64::decode_from(decoder)?;
 
-        return Some((Self::from_le_bytes(bytes_array), &buffer[BYTES..]));
+        return Self::try_from(v).map_err(|_| DecodeError::IntegerOverflow { value: v });
     }
 }
 
-impl Decodable for i32 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        const BYTES: usize = 4;
-
-        if buffer.len() < BYTES {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; BYTES];
+#[cfg(feature = "alloc")]
+macro_rules! impl_decodable_pointer {
+    ($($pointer:ident),+) => {
+        $(
+            impl<T: Decodable> Decodable for $pointer<T> {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    decoder.claim_alloc(size_of::<T>())?;
 
-        bytes_array.copy_from_slice(&buffer[0..BYTES]);
-
-        return Some((Self::from_le_bytes(bytes_array), &buffer[BYTES..]));
-    }
+                    return decoder.nested(|decoder| T::decode_from(decoder).map($pointer::new));
+                }
+            }
+        )+
+    };
 }
 
-impl Decodable for i64 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        const BYTES: usize = 8;
-
-        if buffer.len() < BYTES {
-            return None;
-        }
+#[cfg(feature = "alloc")]
+impl_decodable_pointer!(Box, Rc, Arc);
+
+/// Unsized payloads are decoded into an owned `String` or `Vec` before being converted.
+#[cfg(feature = "alloc")]
+macro_rules! impl_decodable_unsized_pointer {
+    ($($pointer:ident),+) => {
+        $(
+            impl Decodable for $pointer<str> {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    return String::decode_from(decoder).map(|v| v.into());
+                }
+            }
 
-        let mut bytes_array = [0u8; BYTES];
+            impl<T> Decodable for $pointer<[T]>
+            where
+                Vec<T>: Decodable,
+            {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    return Vec::<T>::decode_from(decoder).map(|v| v.into());
+                }
+            }
+        )+
+    };
+}
 
-        bytes_array.copy_from_slice(&buffer[0..BYTES]);
+#[cfg(feature = "alloc")]
+impl_decodable_unsized_pointer!(Box, Rc, Arc);
 
-        return Some((Self::from_le_bytes(bytes_array), &buffer[BYTES..]));
+/// Always decodes into the `Owned` variant.
+#[cfg(feature = "alloc")]
+impl<B: ToOwned + ?Sized> Decodable for Cow<'_, B>
+where
+    B::Owned: Decodable,
+{
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return B::Owned::decode_from(decoder).map(Cow::Owned);
     }
 }
 
-impl Decodable for i128 {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        const BYTES: usize = 16;
-
-        if buffer.len() < BYTES {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; BYTES];
-
-        bytes_array.copy_from_slice(&buffer[0..BYTES]);
-
-        return Some((Self::from_le_bytes(bytes_array), &buffer[BYTES..]));
+impl<T: Decodable> Decodable for Cell<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return T::decode_from(decoder).map(Cell::new);
     }
 }
 
-impl Decodable for usize {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 8 {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; 8];
-
-        bytes_array.copy_from_slice(&buffer[0..8]);
-
-        return Some((Self::from_le_bytes(bytes_array), &buffer[8..]));
+impl<T: Decodable> Decodable for RefCell<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return T::decode_from(decoder).map(RefCell::new);
     }
 }
 
-impl Decodable for isize {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 8 {
-            return None;
-        }
-
-        let mut bytes_array = [0u8; 8];
-
-        bytes_array.copy_from_slice(&buffer[0..8]);
+#[cfg(feature = "std")]
+impl<T: Decodable> Decodable for Mutex<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return T::decode_from(decoder).map(Mutex::new);
+    }
+}
 
-        return Some((Self::from_le_bytes(bytes_array), &buffer[8..]));
+#[cfg(feature = "std")]
+impl<T: Decodable> Decodable for RwLock<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return T::decode_from(decoder).map(RwLock::new);
     }
 }
 
-impl<T: Decodable> Decodable for Box<T> {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        return T::decode_from_buf(buffer).map(|(v, a)| (Box::new(v), a));
+/// Decodes `len` elements into a vector. When decoding from a buffer only as many elements as
+/// the remaining input could contain are reserved up front, as the length has been read from the
+/// input.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_elements<T: Decodable>(
+    decoder: &mut Decoder<'_>,
+    len: usize,
+) -> Result<Vec<T>, DecodeError> {
+    let mut vec = Vec::with_capacity(decoder.capacity_hint(len));
+
+    for _ in 0..len {
+        vec.push(T::decode_from(decoder)?);
     }
+
+    return Ok(vec);
 }
 
-#[cfg(feature = "bool_arr_optimization")]
+#[cfg(all(feature = "alloc", feature = "bool_arr_optimization"))]
 impl<T: Decodable + Any + Clone> Decodable for Vec<T> {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = decoder.read_length::<T>()?;
+
         if TypeId::of::<T>() == TypeId::of::<bool>() {
-            let (len, buffer) = usize::decode_from_buf(buffer)?;
+            let bytes = decoder.read_byte_vec(len.div_ceil(8))?;
             let mut res = Vec::with_capacity(len);
 
-            let bytes = len / 8 + if len % 8 != 0 { 1 } else { 0 };
-            let mut t = 0;
-
-            for i in 0..bytes {
-                let b = buffer[i];
-
+            for b in bytes {
                 for i in 0..8 {
-                    if t >= len {
+                    if res.len() >= len {
                         break;
                     }
 
-                    if b & (1 << i) != 0 {
-                        res.push(true);
-                    } else {
-                        res.push(false);
-                    }
-
-                    t += 1;
+                    res.push(b & (1 << i) != 0);
                 }
             }
 
@@ -339,158 +445,309 @@ impl<T: Decodable + Any + Clone> Decodable for Vec<T> {
             let rp = (res.as_mut_ptr() as *mut T, res.len(), res.capacity());
             let res = unsafe { Vec::from_raw_parts(rp.0, rp.1, rp.2) };
 
-            return Some((res, &buffer[bytes..]));
+            return Ok(res);
         } else {
-            let (len, mut buffer) = usize::decode_from_buf(buffer)?;
-
-            let mut vec = Vec::with_capacity(len);
-
-            for _ in 0..len {
-                let res = T::decode_from_buf(buffer)?;
-
-                vec.push(res.0);
-                buffer = res.1;
-            }
-
-            return Some((vec, buffer));
+            return decoder.nested(|decoder| decode_elements(decoder, len));
         }
     }
 }
 
-#[cfg(not(feature = "bool_arr_optimization"))]
+#[cfg(all(feature = "alloc", not(feature = "bool_arr_optimization")))]
 impl<T: Decodable> Decodable for Vec<T> {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        let (len, mut buffer) = usize::decode_from_buf(buffer)?;
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = decoder.read_length::<T>()?;
 
-        let mut vec = Vec::with_capacity(len);
-
-        for _ in 0..len {
-            let res = T::decode_from_buf(buffer)?;
+        return decoder.nested(|decoder| decode_elements(decoder, len));
+    }
+}
 
-            vec.push(res.0);
-            buffer = res.1;
+/// Decodes `N` elements into an array without allocating.
+fn decode_array<T: Decodable, const N: usize>(
+    decoder: &mut Decoder<'_>,
+) -> Result<[T; N], DecodeError> {
+    let mut err = None;
+    let arr: [Option<T>; N] = core::array::from_fn(|_| {
+        if err.is_some() {
+            return None;
         }
 
-        return Some((vec, buffer));
+        return T::decode_from(decoder).map_err(|e| err = Some(e)).ok();
+    });
+
+    if let Some(e) = err {
+        return Err(e);
     }
+
+    return Ok(arr.map(|v| v.expect("every element was decoded")));
 }
 
 #[cfg(feature = "bool_arr_optimization")]
 impl<T: Decodable + Any + Clone, const N: usize> Decodable for [T; N] {
-    fn decode_from_buf(mut buffer: &[u8]) -> Option<(Self, &[u8])> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
         if TypeId::of::<T>() == TypeId::of::<bool>() {
-            let mut res = Box::new([false; N]);
+            // An array of N bytes always has room for the N / 8 bytes of packed values
+            let mut bytes = [0u8; N];
+            decoder.read_into(&mut bytes[..N.div_ceil(8)])?;
 
-            let bytes = N / 8 + if N % 8 != 0 { 1 } else { 0 };
-            let mut t = 0;
+            let res: [bool; N] = core::array::from_fn(|t| bytes[t / 8] & (1 << (t % 8)) != 0);
+            let res = ManuallyDrop::new(res);
 
-            for i in 0..bytes {
-                let b = buffer[i];
+            return Ok(unsafe { core::ptr::read(&*res as *const [bool; N] as *const [T; N]) });
+        } else {
+            return decoder.nested(decode_array);
+        }
+    }
+}
 
-                for i in 0..8 {
-                    if t >= N {
-                        break;
-                    }
+#[cfg(not(feature = "bool_arr_optimization"))]
+impl<T: Decodable, const N: usize> Decodable for [T; N] {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return decoder.nested(decode_array);
+    }
+}
 
-                    if b & (1 << i) != 0 {
-                        res[t] = true;
-                    }
+impl Decodable for bool {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let v = u8::decode_from(decoder)?;
+
+        return match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::InvalidBool { value: v }),
+        };
+    }
+}
 
-                    t += 1;
-                }
-            }
+impl<T: Decodable> Decodable for Option<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let present = u8::decode_from(decoder)?;
+
+        return match present {
+            0 => Ok(None),
+            1 => decoder.nested(|decoder| T::decode_from(decoder).map(Some)),
+            _ => Err(DecodeError::UnknownEnumTag {
+                tag: present as u128,
+            }),
+        };
+    }
+}
 
-            let b = unsafe { Box::from_raw(Box::into_raw(res) as *mut [T; N]) };
+impl<T: Decodable, E: Decodable> Decodable for Result<T, E> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let tag = u8::decode_from(decoder)?;
 
-            return Some((*b, &buffer[bytes..]));
-        } else {
-            let mut vec = Vec::with_capacity(N);
+        return match tag {
+            0 => decoder.nested(|decoder| T::decode_from(decoder).map(Ok)),
+            1 => decoder.nested(|decoder| E::decode_from(decoder).map(Err)),
+            _ => Err(DecodeError::UnknownEnumTag { tag: tag as u128 }),
+        };
+    }
+}
 
-            for _ in 0..N {
-                let res = T::decode_from_buf(buffer)?;
+#[cfg(feature = "alloc")]
+impl Decodable for String {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = decoder.read_length::<u8>()?;
 
-                vec.push(res.0);
-                buffer = res.1;
+        return String::from_utf8(decoder.read_byte_vec(len)?).map_err(|e| {
+            DecodeError::InvalidUtf8 {
+                valid_up_to: e.utf8_error().valid_up_to(),
             }
+        });
+    }
+}
 
-            return Some((vec.try_into().ok()?, buffer));
-        }
+impl Decodable for char {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let value = u32::decode_from(decoder)?;
+
+        return char::from_u32(value).ok_or(DecodeError::InvalidChar { value });
     }
 }
 
-#[cfg(not(feature = "bool_arr_optimization"))]
-impl<T: Decodable, const N: usize> Decodable for [T; N] {
-    fn decode_from_buf(mut buffer: &[u8]) -> Option<(Self, &[u8])> {
-        let mut vec = Vec::with_capacity(N);
+macro_rules! impl_decodable_non_zero {
+    ($($ty:ident $inner:ty),+) => {
+        $(
+            impl Decodable for $ty {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    let value = <$inner>::decode_from(decoder)?;
 
-        for _ in 0..N {
-            let res = T::decode_from_buf(buffer)?;
+                    return $ty::new(value).ok_or(DecodeError::InvalidNonZero);
+                }
+            }
+        )+
+    };
+}
 
-            vec.push(res.0);
-            buffer = res.1;
-        }
+impl_decodable_non_zero!(
+    NonZeroU8 u8,
+    NonZeroU16 u16,
+    NonZeroU32 u32,
+    NonZeroU64 u64,
+    NonZeroU128 u128,
+    NonZeroUsize usize,
+    NonZeroI8 i8,
+    NonZeroI16 i16,
+    NonZeroI32 i32,
+    NonZeroI64 i64,
+    NonZeroI128 i128,
+    NonZeroIsize isize
+);
+
+impl<T: ?Sized> Decodable for PhantomData<T> {
+    fn decode_from(_decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return Ok(PhantomData);
+    }
+}
 
-        return Some((vec.try_into().ok()?, buffer));
+impl<T: Decodable> Decodable for Wrapping<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return T::decode_from(decoder).map(Wrapping);
     }
 }
 
-impl Decodable for bool {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        if buffer.len() < 1 {
-            return None;
-        }
+impl<T: Decodable> Decodable for Saturating<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return T::decode_from(decoder).map(Saturating);
+    }
+}
 
-        let (v, buffer) = u8::decode_from_buf(buffer)?;
+impl<T: Decodable> Decodable for Reverse<T> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return T::decode_from(decoder).map(Reverse);
+    }
+}
 
-        return Some((v > 0, buffer));
+impl Decodable for () {
+    fn decode_from(_decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        return Ok(());
     }
 }
 
-impl<T: Decodable> Decodable for Option<T> {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        let (present, buffer) = u8::decode_from_buf(buffer)?;
+macro_rules! impl_decodable_tuple {
+    ($($name:ident $var:ident),+) => {
+        impl<$($name: Decodable),+> Decodable for ($($name,)+) {
+            fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                $(let $var = $name::decode_from(decoder)?;)+
 
-        if present == 0 {
-            return Some((None, buffer));
-        } else {
-            return T::decode_from_buf(buffer).map(|(s, p)| (Some(s), p));
+                return Ok(($($var,)+));
+            }
         }
+    };
+}
+
+impl_decodable_tuple!(A a);
+impl_decodable_tuple!(A a, B b);
+impl_decodable_tuple!(A a, B b, C c);
+impl_decodable_tuple!(A a, B b, C c, D d);
+impl_decodable_tuple!(A a, B b, C c, D d, E e);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o);
+impl_decodable_tuple!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o, P p);
+
+#[cfg(feature = "std")]
+impl<K: Decodable + Eq + Hash, V: Decodable, S: BuildHasher + Default> Decodable
+    for HashMap<K, V, S>
+{
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let length = decoder.read_length::<(K, V)>()?;
+
+        return decoder.nested(|decoder| {
+            let mut map = Self::with_hasher(S::default());
+
+            for _ in 0..length {
+                let key = K::decode_from(decoder)?;
+                let value = V::decode_from(decoder)?;
+
+                map.insert(key, value);
+            }
+
+            return Ok(map);
+        });
     }
 }
 
-impl Decodable for String {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        let (len, buffer) = u64::decode_from_buf(buffer)?;
+#[cfg(feature = "alloc")]
+impl<K: Decodable + Ord, V: Decodable> Decodable for BTreeMap<K, V> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let length = decoder.read_length::<(K, V)>()?;
+
+        return decoder.nested(|decoder| {
+            let mut map = Self::new();
 
-        return Some((
-            String::from_utf8(buffer[0..len as usize].to_vec()).ok()?,
-            &buffer[len as usize..],
-        ));
+            for _ in 0..length {
+                let key = K::decode_from(decoder)?;
+                let value = V::decode_from(decoder)?;
+
+                map.insert(key, value);
+            }
+
+            return Ok(map);
+        });
     }
 }
 
 #[cfg(feature = "std")]
-impl<K: Decodable + Eq + Hash, V: Decodable> Decodable for HashMap<K, V> {
-    fn decode_from_buf(buffer: &[u8]) -> Option<(Self, &[u8])> {
-        let (length, mut buffer) = usize::decode_from_buf(buffer)?;
-
-        let mut map = Self::new();
+impl<T: Decodable + Eq + Hash, S: BuildHasher + Default> Decodable for HashSet<T, S> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let length = decoder.read_length::<T>()?;
 
-        for _ in 0..length {
-            let (key, buf) = K::decode_from_buf(buffer)?;
-            let (value, buf) = V::decode_from_buf(buf)?;
-            buffer = buf;
+        return decoder.nested(|decoder| {
+            let mut set = Self::with_hasher(S::default());
 
-            map.insert(key, value);
-        }
+            for _ in 0..length {
+                set.insert(T::decode_from(decoder)?);
+            }
 
-        return Some((map, buffer));
+            return Ok(set);
+        });
     }
 }
 
-#[cfg(test)]
+#[cfg(feature = "alloc")]
+macro_rules! impl_decodable_sequence {
+    ($($collection:ident $method:ident $(+ $bound:ident)*),+) => {
+        $(
+            impl<T: Decodable $(+ $bound)*> Decodable for $collection<T> {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    let length = decoder.read_length::<T>()?;
+
+                    return decoder.nested(|decoder| {
+                        let mut collection = Self::new();
+
+                        for _ in 0..length {
+                            collection.$method(T::decode_from(decoder)?);
+                        }
+
+                        return Ok(collection);
+                    });
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_decodable_sequence!(
+    BTreeSet insert + Ord,
+    BinaryHeap push + Ord,
+    VecDeque push_back,
+    LinkedList push_back
+);
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
-    use crate::Encodable;
+    use crate::{Encodable, IntEncoding};
+    #[cfg(feature = "std")]
+    use crate::{VarInt, ZigZag};
 
     use super::*;
 
@@ -536,6 +793,279 @@ mod tests {
         assert_eq!(b, res);
     }
 
+    #[test]
+    fn test_f32() {
+        for b in [1.5f32, -0.0, f32::MIN_POSITIVE, f32::INFINITY] {
+            let encoded = b.encoded();
+            let res: f32 = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(b.to_bits(), res.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_f64() {
+        for b in [1.5f64, -0.0, f64::MAX, f64::NEG_INFINITY] {
+            let encoded = b.encoded();
+            let res: f64 = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(b.to_bits(), res.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_nan_payload() {
+        let b = f64::from_bits(0xfff8_0000_dead_beef);
+        let encoded = b.encoded();
+        let res: f64 = Decodable::decode(&encoded).unwrap();
+
+        assert!(res.is_nan());
+        assert_eq!(b.to_bits(), res.to_bits());
+
+        let b = f32::from_bits(0x7fc0_1234);
+        let encoded = b.encoded();
+        let res: f32 = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b.to_bits(), res.to_bits());
+    }
+
+    #[test]
+    fn test_char() {
+        for b in ['a', '\u{0}', '\u{1F980}', char::MAX] {
+            let encoded = b.encoded();
+            let res: char = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(b, res);
+        }
+    }
+
+    #[test]
+    fn test_invalid_char() {
+        assert_eq!(
+            char::decode(&0xd800u32.encoded()),
+            Err(DecodeError::InvalidChar { value: 0xd800 })
+        );
+        assert_eq!(
+            char::decode(&0x110000u32.encoded()),
+            Err(DecodeError::InvalidChar { value: 0x110000 })
+        );
+    }
+
+    #[test]
+    fn test_non_zero() {
+        let b = NonZeroU64::new(u64::MAX).unwrap();
+        let encoded = b.encoded();
+        let res: NonZeroU64 = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+
+        let b = NonZeroIsize::new(-5).unwrap();
+        let encoded = b.encoded();
+        let res: NonZeroIsize = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_non_zero_rejects_zero() {
+        assert_eq!(
+            NonZeroU32::decode(&[0, 0, 0, 0]),
+            Err(DecodeError::InvalidNonZero)
+        );
+        assert_eq!(NonZeroI8::decode(&[0]), Err(DecodeError::InvalidNonZero));
+    }
+
+    #[test]
+    fn test_wrappers() {
+        let (res, buffer) = PhantomData::<String>::try_decode_from_buf(&[1]).unwrap();
+
+        assert_eq!(res, PhantomData);
+        assert_eq!(buffer, &[1]);
+
+        let b = (
+            Wrapping(7u8),
+            Saturating(-3i32),
+            Reverse("test".to_string()),
+        );
+        let encoded = b.encoded();
+        let res: (Wrapping<u8>, Saturating<i32>, Reverse<String>) =
+            Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_unit() {
+        let (res, buffer) = <()>::try_decode_from_buf(&[1, 2]).unwrap();
+
+        assert_eq!(res, ());
+        assert_eq!(buffer, &[1, 2]);
+    }
+
+    #[test]
+    fn test_tuple() {
+        let b = (1u8, "test".to_string(), (2u16, true));
+        let encoded = b.encoded();
+        let res: (u8, String, (u16, bool)) = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_tuple_16() {
+        type Tuple16 = (
+            u8,
+            u16,
+            u32,
+            u64,
+            u128,
+            i8,
+            i16,
+            i32,
+            i64,
+            i128,
+            usize,
+            isize,
+            bool,
+            u8,
+            (),
+            u8,
+        );
+
+        let b: Tuple16 = (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, true, 14, (), 15);
+        let encoded = b.encoded();
+        let res: Tuple16 = Decodable::decode(&encoded).unwrap();
+
+        // The standard library only implements PartialEq for tuples of up to 12 elements
+        assert_eq!(res.encoded(), encoded);
+        assert_eq!(res.0, 0);
+        assert_eq!(res.15, 15);
+    }
+
+    #[test]
+    fn test_vec_tuple() {
+        let b = vec![(1u32, "a".to_string()), (2, "b".to_string())];
+        let encoded = b.encoded();
+        let res: Vec<(u32, String)> = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_btree_map() {
+        let b = BTreeMap::from([(1u8, "a".to_string()), (2u8, "b".to_string())]);
+        let encoded = b.encoded();
+        let res: BTreeMap<u8, String> = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_sequences() {
+        let b = BTreeSet::from([3u16, 1, 2]);
+        let res: BTreeSet<u16> = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+
+        let b = VecDeque::from([3u16, 1, 2]);
+        let res: VecDeque<u16> = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+
+        let b = LinkedList::from([3u16, 1, 2]);
+        let res: LinkedList<u16> = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+
+        let b = BinaryHeap::from([3u16, 1, 2]);
+        let res: BinaryHeap<u16> = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b.into_sorted_vec(), res.into_sorted_vec());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hash_collections() {
+        use std::collections::hash_map::RandomState;
+        use std::hash::BuildHasherDefault;
+
+        #[derive(Default)]
+        struct XorHasher(u64);
+
+        impl std::hash::Hasher for XorHasher {
+            fn finish(&self) -> u64 {
+                return self.0;
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for b in bytes {
+                    self.0 = self.0.rotate_left(8) ^ *b as u64;
+                }
+            }
+        }
+
+        let b: HashMap<u8, u32, BuildHasherDefault<XorHasher>> =
+            [(1, 10), (2, 20)].into_iter().collect();
+        let res: HashMap<u8, u32, BuildHasherDefault<XorHasher>> =
+            Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+
+        let b: HashSet<String, RandomState> = ["a".to_string(), "b".to_string()].into();
+        let res: HashSet<String> = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_pointers() {
+        let encoded = "test".encoded();
+
+        assert_eq!(&*Box::<str>::decode(&encoded).unwrap(), "test");
+        assert_eq!(&*Rc::<str>::decode(&encoded).unwrap(), "test");
+        assert_eq!(&*Arc::<str>::decode(&encoded).unwrap(), "test");
+        assert_eq!(Cow::<str>::decode(&encoded).unwrap(), "test");
+
+        let b: Arc<[u16]> = Arc::from([1u16, 2, 3].as_slice());
+        let res: Arc<[u16]> = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+
+        let b: Rc<[bool]> = Rc::from([true, false, true].as_slice());
+        let res: Rc<[bool]> = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+
+        let b = (
+            Rc::new(5u8),
+            Arc::new(6u16),
+            Box::<[u8]>::from([7u8].as_slice()),
+        );
+        let res: (Rc<u8>, Arc<u16>, Box<[u8]>) = Decodable::decode(&b.encoded()).unwrap();
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_cells() {
+        let b = (Cell::new(1u8), RefCell::new("a".to_string()));
+        let res: (Cell<u8>, RefCell<String>) = Decodable::decode(&b.encoded()).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_locks() {
+        let encoded = 9u32.encoded();
+
+        assert_eq!(
+            Mutex::<u32>::decode(&encoded)
+                .unwrap()
+                .into_inner()
+                .unwrap(),
+            9
+        );
+        assert_eq!(
+            RwLock::<u32>::decode(&encoded)
+                .unwrap()
+                .into_inner()
+                .unwrap(),
+            9
+        );
+    }
+
     #[test]
     fn test_string() {
         let b = "test";
@@ -563,6 +1093,29 @@ mod tests {
         assert_eq!(b, res);
     }
 
+    #[test]
+    fn test_result() {
+        let b: Result<String, u16> = Ok("test".to_string());
+        let encoded = b.encoded();
+        let res: Result<String, u16> = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+
+        let b: Result<String, u16> = Err(404);
+        let encoded = b.encoded();
+        let res: Result<String, u16> = Decodable::decode(&encoded).unwrap();
+
+        assert_eq!(b, res);
+    }
+
+    #[test]
+    fn test_invalid_result_tag() {
+        assert_eq!(
+            Result::<u8, u8>::decode(&[2, 0]),
+            Err(DecodeError::UnknownEnumTag { tag: 2 })
+        );
+    }
+
     #[test]
     fn test_bool_array() {
         let b = [true, false, true, false, true, false, true, false];
@@ -652,4 +1205,327 @@ mod tests {
 
         assert_eq!(b, res);
     }
+
+    #[test]
+    fn test_invalid_bool() {
+        assert_eq!(
+            bool::decode(&[2]),
+            Err(DecodeError::InvalidBool { value: 2 })
+        );
+    }
+
+    #[test]
+    fn test_invalid_option_tag() {
+        assert_eq!(
+            Option::<u8>::decode(&[2, 0]),
+            Err(DecodeError::UnknownEnumTag { tag: 2 })
+        );
+    }
+
+    #[test]
+    fn test_unexpected_end() {
+        assert_eq!(
+            u64::decode(&[1, 2, 3]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 8,
+                available: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let encoded = [2, 0, 0, 0, 0, 0, 0, 0, b'a', 0xff];
+
+        assert_eq!(
+            String::decode(&encoded),
+            Err(DecodeError::InvalidUtf8 { valid_up_to: 1 })
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_legacy_decode_from_buf() {
+        assert_eq!(u16::decode_from_buf(&[4, 0, 1]), Some((4, &[1][..])));
+        assert_eq!(u16::decode_from_buf(&[4]), None);
+    }
+
+    #[test]
+    fn test_truncated_string() {
+        let encoded = "test".encoded();
+
+        assert_eq!(
+            String::decode(&encoded[..10]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 4,
+                available: 2
+            })
+        );
+        assert_eq!(
+            String::decode_with_limits(
+                &[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0],
+                DecodeLimits::unlimited()
+            ),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 0xffffffff,
+                available: 0
+            })
+        );
+    }
+
+    #[cfg(feature = "bool_arr_optimization")]
+    #[test]
+    fn test_truncated_bool_arr() {
+        let encoded = vec![true; 20].encoded();
+
+        assert_eq!(
+            Vec::<bool>::decode(&encoded[..10]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 3,
+                available: 2
+            })
+        );
+        assert_eq!(
+            <[bool; 20]>::decode(&encoded[8..10]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 3,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_hostile_length() {
+        let encoded = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 1];
+
+        assert!(Vec::<u64>::decode(&encoded).is_err());
+        assert!(Vec::<bool>::decode(&encoded).is_err());
+        assert!(BTreeMap::<u8, u8>::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_collection_limit() {
+        let encoded = [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0];
+
+        assert_eq!(
+            String::decode(&encoded),
+            Err(DecodeError::CollectionTooLong {
+                length: 0xffffffff,
+                max: 1 << 24
+            })
+        );
+        assert_eq!(
+            Vec::<()>::decode(&encoded),
+            Err(DecodeError::CollectionTooLong {
+                length: 0xffffffff,
+                max: 1 << 24
+            })
+        );
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let limits = DecodeLimits::new().with_max_depth(2);
+
+        assert_eq!(
+            Box::<Box<Box<u8>>>::decode_with_limits(&[1], limits),
+            Err(DecodeError::DepthLimit { max: 2 })
+        );
+        assert_eq!(
+            Box::<Box<u8>>::decode_with_limits(&[1], limits),
+            Ok(Box::new(Box::new(1)))
+        );
+    }
+
+    #[test]
+    fn test_alloc_limit() {
+        let limits = DecodeLimits::new().with_max_alloc(size_of::<Vec<u32>>() * 2 + 12);
+        let encoded = vec![vec![1u32, 2], vec![3, 4]].encoded();
+
+        assert_eq!(
+            Vec::<Vec<u32>>::decode_with_limits(&encoded, limits),
+            Err(DecodeError::AllocationLimit {
+                requested: 8,
+                remaining: 4
+            })
+        );
+        assert!(Vec::<Vec<u32>>::decode(&encoded).is_ok());
+    }
+
+    #[test]
+    fn test_decode_with_config() {
+        let config = Config::new()
+            .with_endian(Endian::Big)
+            .with_length_encoding(IntEncoding::VarInt);
+        let value = (vec![0x0102u16, 3], "abc".to_string(), -5i64, 2.5f64);
+        let encoded = value.encoded_with_config(config).unwrap();
+
+        assert_eq!(&encoded[..5], &[2, 1, 2, 0, 3]);
+        assert_eq!(Decodable::decode_with_config(&encoded, config), Ok(value));
+        assert!(<(Vec<u16>, String, i64, f64)>::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_invalid_varint_length() {
+        let config = Config::new().with_length_encoding(IntEncoding::VarInt);
+
+        assert_eq!(
+            Vec::<u8>::decode_with_config(&[0x81, 0], config),
+            Err(DecodeError::InvalidVarInt)
+        );
+        assert_eq!(
+            String::decode_with_config(&[0xff; 11], config),
+            Err(DecodeError::InvalidVarInt)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_from_reader() {
+        let value = (
+            vec![1u16, 2],
+            "abc".to_string(),
+            HashMap::from([(1u8, vec![true, false, true])]),
+            [Some('a'), None],
+        );
+        let mut encoded = value.encoded();
+        encoded.extend_from_slice(&5u32.encoded());
+
+        let mut reader = std::io::Cursor::new(&encoded);
+
+        assert_eq!(Decodable::decode_from_reader(&mut reader), Ok(value));
+        assert_eq!(u32::decode_from_reader(&mut reader), Ok(5));
+        assert_eq!(
+            u8::decode_from_reader(&mut reader),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 1,
+                available: 0
+            })
+        );
+
+        let encoded = "test".encoded();
+
+        assert_eq!(
+            String::decode_from_reader(&encoded[..10]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 4,
+                available: 2
+            })
+        );
+        assert_eq!(
+            Vec::<u64>::decode_from_reader(&[0xff; 8][..]),
+            Err(DecodeError::CollectionTooLong {
+                length: usize::MAX,
+                max: 1 << 24
+            })
+        );
+    }
+
+    /// A small xorshift generator, so the robustness test is reproducible without extra
+    /// dependencies.
+    #[cfg(feature = "std")]
+    fn random_buffers() -> Vec<Vec<u8>> {
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            return state;
+        };
+
+        return (0..2000)
+            .map(|_| {
+                let len = (next() % 64) as usize;
+                let mut buffer: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+
+                // Keep some length prefixes small so the decoders get past the first read
+                if next() % 2 == 0 {
+                    for b in buffer.iter_mut().skip(1).take(7) {
+                        *b = 0;
+                    }
+                }
+
+                return buffer;
+            })
+            .collect();
+    }
+
+    /// Feeds random buffers and every truncation of a valid encoding to each type. Decoding may
+    /// fail but must never panic, and a truncated encoding must always be rejected.
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hostile_input() {
+        let random = random_buffers();
+
+        macro_rules! check_type {
+            ($($value:expr => $ty:ty),+ $(,)?) => {
+                $(
+                    let encoded = $value.encoded();
+
+                    assert!(<$ty>::decode(&encoded).is_ok());
+
+                    for len in 0..encoded.len() {
+                        assert!(
+                            <$ty>::decode(&encoded[..len]).is_err(),
+                            "{} decoded from {} of {} bytes",
+                            stringify!($ty),
+                            len,
+                            encoded.len()
+                        );
+                    }
+
+                    for buffer in random.iter() {
+                        let _ = <$ty>::decode(buffer);
+                    }
+                )+
+            };
+        }
+
+        check_type!(
+            7u8 => u8,
+            7u16 => u16,
+            7u32 => u32,
+            7u64 => u64,
+            7u128 => u128,
+            -7i8 => i8,
+            -7i16 => i16,
+            -7i32 => i32,
+            -7i64 => i64,
+            -7i128 => i128,
+            7usize => usize,
+            -7isize => isize,
+            1.5f32 => f32,
+            1.5f64 => f64,
+            true => bool,
+            'x' => char,
+            NonZeroU32::new(7).unwrap() => NonZeroU32,
+            "test".to_string() => String,
+            vec![1u8, 2, 3] => Vec<u8>,
+            vec![true; 11] => Vec<bool>,
+            [true; 11] => [bool; 11],
+            [1u16, 2, 3] => [u16; 3],
+            Some("test".to_string()) => Option<String>,
+            Err::<u8, String>("e".to_string()) => Result<u8, String>,
+            (1u8, "t".to_string(), false) => (u8, String, bool),
+            Box::<str>::from("test") => Box<str>,
+            Rc::<[u16]>::from([1u16, 2].as_slice()) => Rc<[u16]>,
+            Cow::<str>::Borrowed("test") => Cow<str>,
+            Cell::new(3u8) => Cell<u8>,
+            RefCell::new("t".to_string()) => RefCell<String>,
+            Wrapping(7u32) => Wrapping<u32>,
+            Reverse(-7i16) => Reverse<i16>,
+            Arc::new(vec![false, true]) => Arc<Vec<bool>>,
+            BTreeMap::from([(1u8, "a".to_string())]) => BTreeMap<u8, String>,
+            HashMap::from([("a".to_string(), 1u32)]) => HashMap<String, u32>,
+            HashSet::from([1u16, 2]) => HashSet<u16>,
+            BTreeSet::from([1u16, 2]) => BTreeSet<u16>,
+            VecDeque::from([1u16, 2]) => VecDeque<u16>,
+            LinkedList::from([1u16, 2]) => LinkedList<u16>,
+            BinaryHeap::from([1u16, 2]) => BinaryHeap<u16>,
+            VarInt(u64::MAX) => VarInt<u64>,
+            VarInt(300u16) => VarInt<u16>,
+            ZigZag(i32::MIN) => ZigZag<i32>,
+        );
+    }
 }
@@ -0,0 +1,753 @@
+use core::fmt::{Debug, Formatter};
+
+use crate::{Config, DecodeError, Endian, IntEncoding};
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+
+/// The resources a single decode operation is allowed to use.
+///
+/// Length prefixes and nesting are controlled by the encoded data, so decoding untrusted input
+/// without limits allows a small message to request an enormous allocation or overflow the
+/// stack. Every [Decodable](crate::Decodable) implementation provided by this crate, as well as
+/// the code generated by the derive macros, enforces these limits.
+///
+/// The default limits are intended to be safe for untrusted input:
+/// * 64 MiB allocated in total
+/// * 16,777,216 elements in a single collection
+/// * 128 nested values
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Decodable, DecodeError, DecodeLimits};
+///
+/// let limits = DecodeLimits::default().with_max_collection_len(2);
+/// let encoded = vec![3, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3];
+///
+/// assert_eq!(
+///     Vec::<u8>::decode_with_limits(&encoded, limits),
+///     Err(DecodeError::CollectionTooLong { length: 3, max: 2 })
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DecodeLimits {
+    max_alloc: usize,
+    max_collection_len: usize,
+    max_depth: usize,
+}
+
+impl DecodeLimits {
+    /// Creates the default limits.
+    pub const fn new() -> Self {
+        return Self {
+            max_alloc: 64 * 1024 * 1024,
+            max_collection_len: 1 << 24,
+            max_depth: 128,
+        };
+    }
+
+    /// Creates limits which allow any input. This should only be used with trusted input.
+    pub const fn unlimited() -> Self {
+        return Self {
+            max_alloc: usize::MAX,
+            max_collection_len: usize::MAX,
+            max_depth: usize::MAX,
+        };
+    }
+
+    /// Sets the maximum number of bytes which can be allocated in total.
+    pub const fn with_max_alloc(mut self, max_alloc: usize) -> Self {
+        self.max_alloc = max_alloc;
+
+        return self;
+    }
+
+    /// Sets the maximum number of elements in a single collection or string.
+    pub const fn with_max_collection_len(mut self, max_collection_len: usize) -> Self {
+        self.max_collection_len = max_collection_len;
+
+        return self;
+    }
+
+    /// Sets the maximum number of values which can be nested inside each other.
+    pub const fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+
+        return self;
+    }
+
+    /// Returns the maximum number of bytes which can be allocated in total.
+    pub const fn max_alloc(&self) -> usize {
+        return self.max_alloc;
+    }
+
+    /// Returns the maximum number of elements in a single collection or string.
+    pub const fn max_collection_len(&self) -> usize {
+        return self.max_collection_len;
+    }
+
+    /// Returns the maximum number of values which can be nested inside each other.
+    pub const fn max_depth(&self) -> usize {
+        return self.max_depth;
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// The state of a decode operation, the unprocessed input, the configuration being used and the
+/// resources used so far.
+///
+/// A decoder is passed to [Decodable::decode_from](crate::Decodable::decode_from), which reads
+/// from it using the methods below. The lifetime `'de` is the lifetime of the input buffer, which
+/// allows [BorrowDecodable](crate::BorrowDecodable) implementations to borrow from it.
+///
+/// The input is either a buffer or, with the `std` feature, a `std::io::Read`. A decoder over a
+/// reader reads exactly the bytes each value requires, values which borrow from the input or
+/// which use the `pre_dec_func` and `post_dec_func` hooks cannot be decoded from a reader.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{Decodable, Decoder};
+///
+/// let mut decoder = Decoder::new(&[1, 0, 2, 0]);
+///
+/// assert_eq!(u16::decode_from(&mut decoder), Ok(1));
+/// assert_eq!(u16::decode_from(&mut decoder), Ok(2));
+/// assert!(decoder.remaining().is_empty());
+/// ```
+pub struct Decoder<'de> {
+    input: Input<'de>,
+    config: Config,
+    allocated: usize,
+    depth: usize,
+    // The number of bytes left in the value being read by `limited`, only used for readers
+    // since a buffer can be shortened instead
+    limit: Option<usize>,
+}
+
+enum Input<'de> {
+    Buffer(&'de [u8]),
+    #[cfg(feature = "std")]
+    Reader(&'de mut dyn std::io::Read),
+}
+
+impl<'de> Decoder<'de> {
+    /// Creates a decoder over the buffer using the default configuration.
+    pub fn new(buffer: &'de [u8]) -> Self {
+        return Self::with_config(buffer, Config::default());
+    }
+
+    /// Creates a decoder over the buffer using the default configuration with the specified
+    /// limits.
+    pub fn with_limits(buffer: &'de [u8], limits: DecodeLimits) -> Self {
+        return Self::with_config(buffer, Config::default().with_limits(limits));
+    }
+
+    /// Creates a decoder over the buffer using the specified configuration.
+    pub fn with_config(buffer: &'de [u8], config: Config) -> Self {
+        return Self::with_input(Input::Buffer(buffer), config);
+    }
+
+    /// Creates a decoder which reads from the reader using the default configuration.
+    #[cfg(feature = "std")]
+    pub fn from_reader(reader: &'de mut dyn std::io::Read) -> Self {
+        return Self::from_reader_with_config(reader, Config::default());
+    }
+
+    /// Creates a decoder which reads from the reader using the specified configuration.
+    #[cfg(feature = "std")]
+    pub fn from_reader_with_config(reader: &'de mut dyn std::io::Read, config: Config) -> Self {
+        return Self::with_input(Input::Reader(reader), config);
+    }
+
+    fn with_input(input: Input<'de>, config: Config) -> Self {
+        return Self {
+            input,
+            config,
+            allocated: 0,
+            depth: 0,
+            limit: None,
+        };
+    }
+
+    /// Returns the configuration used by this decoder.
+    pub fn config(&self) -> &Config {
+        return &self.config;
+    }
+
+    /// Returns the limits used by this decoder.
+    pub fn limits(&self) -> &DecodeLimits {
+        return self.config.limits();
+    }
+
+    /// Returns the bytes which have not been decoded yet. When decoding from a reader nothing
+    /// has been read ahead, so this is empty.
+    pub fn remaining(&self) -> &'de [u8] {
+        return match self.input {
+            Input::Buffer(buffer) => buffer,
+            #[cfg(feature = "std")]
+            Input::Reader(_) => &[],
+        };
+    }
+
+    /// Returns the bytes which have not been decoded yet, or [DecodeError::RequiresBuffer] when
+    /// decoding from a reader.
+    pub fn try_remaining(&self) -> Result<&'de [u8], DecodeError> {
+        return match self.input {
+            Input::Buffer(buffer) => Ok(buffer),
+            #[cfg(feature = "std")]
+            Input::Reader(_) => Err(DecodeError::RequiresBuffer),
+        };
+    }
+
+    /// Replaces the bytes which have not been decoded yet. This is used to apply the
+    /// `pre_dec_func` and `post_dec_func` hooks, which may return a different slice. When
+    /// decoding from a reader the reader is replaced by the buffer.
+    pub fn set_remaining(&mut self, buffer: &'de [u8]) {
+        self.input = Input::Buffer(buffer);
+    }
+
+    /// Reads the next `len` bytes, borrowing them from the input. When decoding from a reader
+    /// [DecodeError::RequiresBuffer] is returned, `read_into` or `read_byte_vec` should be used
+    /// by values which do not need to borrow.
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'de [u8], DecodeError> {
+        let buffer = self.try_remaining()?;
+
+        if buffer.len() < len {
+            return Err(DecodeError::UnexpectedEnd {
+                needed: len,
+                available: buffer.len(),
+            });
+        }
+
+        let (bytes, rest) = buffer.split_at(len);
+        self.input = Input::Buffer(rest);
+
+        return Ok(bytes);
+    }
+
+    /// Fills `buf` with the next `buf.len()` bytes.
+    pub fn read_into(&mut self, buf: &mut [u8]) -> Result<(), DecodeError> {
+        match self.input {
+            Input::Buffer(_) => {
+                buf.copy_from_slice(self.read_bytes(buf.len())?);
+            }
+            #[cfg(feature = "std")]
+            Input::Reader(ref mut reader) => {
+                if let Some(limit) = self.limit {
+                    if buf.len() > limit {
+                        return Err(DecodeError::UnexpectedEnd {
+                            needed: buf.len(),
+                            available: limit,
+                        });
+                    }
+                }
+
+                let mut filled = 0;
+
+                while filled < buf.len() {
+                    match reader.read(&mut buf[filled..]) {
+                        Ok(0) => {
+                            return Err(DecodeError::UnexpectedEnd {
+                                needed: buf.len(),
+                                available: filled,
+                            });
+                        }
+                        Ok(n) => filled += n,
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                        Err(e) => return Err(DecodeError::Io(e.kind())),
+                    }
+                }
+
+                self.limit = self.limit.map(|limit| limit - buf.len());
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Discards the next `len` bytes.
+    pub fn skip(&mut self, len: usize) -> Result<(), DecodeError> {
+        match self.input {
+            Input::Buffer(_) => {
+                self.read_bytes(len)?;
+            }
+            #[cfg(feature = "std")]
+            Input::Reader(_) => {
+                let mut scratch = [0u8; 256];
+                let mut left = len;
+
+                while left > 0 {
+                    let n = left.min(scratch.len());
+
+                    self.read_into(&mut scratch[..n])?;
+                    left -= n;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Runs `f` on a decoder which can only read the next `len` bytes, reading past them returns
+    /// [DecodeError::UnexpectedEnd]. Any of the `len` bytes which `f` does not read are skipped,
+    /// so decoding continues after them. This is used to read the values of tagged fields.
+    pub fn limited<T>(
+        &mut self,
+        len: usize,
+        f: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        match self.input {
+            Input::Buffer(buffer) => {
+                if buffer.len() < len {
+                    return Err(DecodeError::UnexpectedEnd {
+                        needed: len,
+                        available: buffer.len(),
+                    });
+                }
+
+                let (value, rest) = buffer.split_at(len);
+                self.input = Input::Buffer(value);
+
+                let res = f(self);
+                self.input = Input::Buffer(rest);
+
+                return res;
+            }
+            #[cfg(feature = "std")]
+            Input::Reader(_) => {
+                let outer = self.limit;
+
+                if let Some(outer) = outer {
+                    if len > outer {
+                        return Err(DecodeError::UnexpectedEnd {
+                            needed: len,
+                            available: outer,
+                        });
+                    }
+                }
+
+                self.limit = Some(len);
+
+                let res = f(self);
+                let unread = self.limit.unwrap_or(0);
+
+                // The unread bytes are counted against the outer limit as they are skipped
+                self.limit = outer.map(|outer| outer - (len - unread));
+
+                let value = res?;
+                self.skip(unread)?;
+
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Whether there is nothing left to read, which is the end of the buffer or, when decoding
+    /// from a reader, the end of the bytes allowed by `limited`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn is_exhausted(&self) -> bool {
+        return match self.input {
+            Input::Buffer(buffer) => buffer.is_empty(),
+            #[cfg(feature = "std")]
+            Input::Reader(_) => self.limit == Some(0),
+        };
+    }
+
+    /// Reads the next `len` bytes into a vector. The length should already have been checked
+    /// against the limits.
+    #[cfg(feature = "alloc")]
+    pub fn read_byte_vec(&mut self, len: usize) -> Result<Vec<u8>, DecodeError> {
+        return match self.input {
+            Input::Buffer(_) => self.read_bytes(len).map(|bytes| bytes.to_vec()),
+            #[cfg(feature = "std")]
+            Input::Reader(_) => {
+                let mut bytes = vec![0; len];
+
+                self.read_into(&mut bytes)?;
+
+                Ok(bytes)
+            }
+        };
+    }
+
+    /// Reads the next `N` bytes into an array.
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let mut bytes_array = [0u8; N];
+
+        self.read_into(&mut bytes_array)?;
+
+        return Ok(bytes_array);
+    }
+
+    /// Returns how many of `len` elements should be reserved up front. A length read from a
+    /// buffer cannot require more elements than there are bytes left, a length read from a
+    /// reader has been checked against the allocation limit.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn capacity_hint(&self, len: usize) -> usize {
+        return match self.input {
+            Input::Buffer(buffer) => len.min(buffer.len()),
+            #[cfg(feature = "std")]
+            Input::Reader(_) => len,
+        };
+    }
+
+    /// Reads the length prefix of a collection which stores elements of type `T`. The length is
+    /// checked against the collection length limit and the memory required for the elements is
+    /// counted against the allocation limit.
+    pub fn read_length<T>(&mut self) -> Result<usize, DecodeError> {
+        let length = self.read_length_prefix()?;
+
+        self.claim_length::<T>(length)?;
+
+        return Ok(length);
+    }
+
+    /// Checks a collection length which was not read using `read_length` against the limits.
+    pub fn claim_length<T>(&mut self, length: usize) -> Result<(), DecodeError> {
+        let max = self.limits().max_collection_len();
+
+        if length > max {
+            return Err(DecodeError::CollectionTooLong { length, max });
+        }
+
+        return self.claim_alloc(length.saturating_mul(core::mem::size_of::<T>()));
+    }
+
+    /// Counts `bytes` against the allocation limit.
+    pub fn claim_alloc(&mut self, bytes: usize) -> Result<(), DecodeError> {
+        let remaining = self.limits().max_alloc() - self.allocated;
+
+        if bytes > remaining {
+            return Err(DecodeError::AllocationLimit {
+                requested: bytes,
+                remaining,
+            });
+        }
+
+        self.allocated += bytes;
+
+        return Ok(());
+    }
+
+    /// Runs `f` one nesting level deeper, failing if the depth limit would be exceeded.
+    pub fn nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        let max = self.limits().max_depth();
+
+        if self.depth >= max {
+            return Err(DecodeError::DepthLimit { max });
+        }
+
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+
+        return res;
+    }
+
+    /// Reads a length prefix using the configured length encoding without checking it against
+    /// the limits. This is used when the data is borrowed rather than allocated.
+    pub fn read_length_prefix(&mut self) -> Result<usize, DecodeError> {
+        let length = self.read_uint(self.config.length_encoding())?;
+
+        return usize::try_from(length).map_err(|_| DecodeError::LengthOverflow { length });
+    }
+
+    /// Reads the tag of an enum using the configured enum tag encoding.
+    pub fn read_enum_tag(&mut self) -> Result<u16, DecodeError> {
+        let tag = self.read_uint(self.config.enum_tag_encoding())?;
+
+        return u16::try_from(tag).map_err(|_| DecodeError::UnknownEnumTag { tag: tag as u128 });
+    }
+
+    fn read_uint(&mut self, encoding: IntEncoding) -> Result<u64, DecodeError> {
+        let endian = self.config.endian();
+
+        return Ok(match encoding {
+            IntEncoding::U8 => self.read_array::<1>()?[0] as u64,
+            IntEncoding::U16 => {
+                let bytes = self.read_array()?;
+
+                match endian {
+                    Endian::Little => u16::from_le_bytes(bytes) as u64,
+                    Endian::Big => u16::from_be_bytes(bytes) as u64,
+                }
+            }
+            IntEncoding::U32 => {
+                let bytes = self.read_array()?;
+
+                match endian {
+                    Endian::Little => u32::from_le_bytes(bytes) as u64,
+                    Endian::Big => u32::from_be_bytes(bytes) as u64,
+                }
+            }
+            IntEncoding::U64 => {
+                let bytes = self.read_array()?;
+
+                match endian {
+                    Endian::Little => u64::from_le_bytes(bytes),
+                    Endian::Big => u64::from_be_bytes(bytes),
+                }
+            }
+            IntEncoding::VarInt => self.read_varint(u64::BITS)? as u64,
+        });
+    }
+
+    /// Reads an unsigned LEB128 variable length integer holding at most `bits` bits. Encodings
+    /// which are longer than necessary or larger than `bits` bits are rejected, so each value
+    /// has exactly one encoding.
+    pub(crate) fn read_varint(&mut self, bits: u32) -> Result<u128, DecodeError> {
+        let max_len = bits.div_ceil(7);
+        let mut value: u128 = 0;
+
+        for i in 0..max_len {
+            let byte = self.read_array::<1>()?[0];
+            let payload = (byte & 0x7f) as u128;
+
+            // The last possible byte can only contain the remaining most significant bits
+            if i == max_len - 1 && payload >> (bits - i * 7) != 0 {
+                return Err(DecodeError::InvalidVarInt);
+            }
+
+            value |= payload << (i * 7);
+
+            if byte & 0x80 == 0 {
+                // A zero final byte is only valid when it is the only byte
+                if byte == 0 && i != 0 {
+                    return Err(DecodeError::InvalidVarInt);
+                }
+
+                return Ok(value);
+            }
+        }
+
+        return Err(DecodeError::InvalidVarInt);
+    }
+}
+
+impl Debug for Decoder<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        return f
+            .debug_struct("Decoder")
+            .field("remaining", &self.remaining())
+            .field("config", &self.config)
+            .field("allocated", &self.allocated)
+            .field("depth", &self.depth)
+            .field("limit", &self.limit)
+            .finish_non_exhaustive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_bytes() {
+        let mut decoder = Decoder::new(&[1, 2, 3]);
+
+        assert_eq!(decoder.read_bytes(2), Ok(&[1u8, 2][..]));
+        assert_eq!(
+            decoder.read_bytes(2),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 2,
+                available: 1
+            })
+        );
+        assert_eq!(decoder.remaining(), &[3]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_from_reader() {
+        struct Interrupting(bool, &'static [u8]);
+
+        impl std::io::Read for Interrupting {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.0 = !self.0;
+
+                if self.0 {
+                    return Err(std::io::ErrorKind::Interrupted.into());
+                }
+
+                return self.1.read(buf);
+            }
+        }
+
+        let mut reader = Interrupting(false, &[1, 2, 3]);
+        let mut decoder = Decoder::from_reader(&mut reader);
+
+        assert_eq!(decoder.read_array(), Ok([1, 2]));
+        assert_eq!(decoder.read_bytes(1), Err(DecodeError::RequiresBuffer));
+        assert_eq!(decoder.try_remaining(), Err(DecodeError::RequiresBuffer));
+        assert_eq!(
+            decoder.read_byte_vec(2),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 2,
+                available: 1
+            })
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reader_error() {
+        struct Failing;
+
+        impl std::io::Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                return Err(std::io::ErrorKind::ConnectionReset.into());
+            }
+        }
+
+        assert_eq!(
+            Decoder::from_reader(&mut Failing).read_array::<1>(),
+            Err(DecodeError::Io(std::io::ErrorKind::ConnectionReset))
+        );
+    }
+
+    #[test]
+    fn test_claim_alloc() {
+        let mut decoder = Decoder::with_limits(&[], DecodeLimits::new().with_max_alloc(10));
+
+        assert!(decoder.claim_alloc(6).is_ok());
+        assert_eq!(
+            decoder.claim_alloc(6),
+            Err(DecodeError::AllocationLimit {
+                requested: 6,
+                remaining: 4
+            })
+        );
+        assert!(decoder.claim_length::<u16>(2).is_ok());
+        assert!(decoder.claim_alloc(1).is_err());
+    }
+
+    #[test]
+    fn test_read_varint() {
+        let mut decoder = Decoder::new(&[0, 127, 0x80, 1, 0xac, 2]);
+
+        assert_eq!(decoder.read_varint(64), Ok(0));
+        assert_eq!(decoder.read_varint(64), Ok(127));
+        assert_eq!(decoder.read_varint(64), Ok(128));
+        assert_eq!(decoder.read_varint(64), Ok(300));
+
+        let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 1];
+        assert_eq!(Decoder::new(&max).read_varint(64), Ok(u64::MAX as u128));
+
+        let too_large = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 2];
+        assert_eq!(
+            Decoder::new(&too_large).read_varint(64),
+            Err(DecodeError::InvalidVarInt)
+        );
+        assert_eq!(Decoder::new(&[0xff, 1]).read_varint(8), Ok(255));
+        assert_eq!(
+            Decoder::new(&[0x80, 2]).read_varint(8),
+            Err(DecodeError::InvalidVarInt)
+        );
+        assert_eq!(
+            Decoder::new(&[0x80, 0x80, 0]).read_varint(16),
+            Err(DecodeError::InvalidVarInt)
+        );
+        assert_eq!(
+            Decoder::new(&[0x80, 0]).read_varint(64),
+            Err(DecodeError::InvalidVarInt)
+        );
+        assert!(Decoder::new(&[0x80]).read_varint(64).is_err());
+    }
+
+    #[test]
+    fn test_read_length_prefix() {
+        let config = Config::new()
+            .with_endian(Endian::Big)
+            .with_length_encoding(IntEncoding::U16);
+
+        assert_eq!(
+            Decoder::with_config(&[1, 2], config).read_length_prefix(),
+            Ok(258)
+        );
+
+        let config = Config::new().with_length_encoding(IntEncoding::VarInt);
+
+        assert_eq!(
+            Decoder::with_config(&[0xac, 2], config).read_length_prefix(),
+            Ok(300)
+        );
+    }
+
+    #[test]
+    fn test_read_enum_tag() {
+        let config = Config::new().with_enum_tag_encoding(IntEncoding::U32);
+
+        assert_eq!(
+            Decoder::with_config(&[0, 0, 1, 0], config).read_enum_tag(),
+            Err(DecodeError::UnknownEnumTag { tag: 65536 })
+        );
+        assert_eq!(Decoder::new(&[5, 0]).read_enum_tag(), Ok(5));
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut decoder = Decoder::with_limits(&[], DecodeLimits::new().with_max_depth(2));
+
+        let res = decoder.nested(|d| d.nested(|d| d.nested(|_| Ok(()))));
+
+        assert_eq!(res, Err(DecodeError::DepthLimit { max: 2 }));
+        assert!(decoder.nested(|d| d.nested(|_| Ok(()))).is_ok());
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut decoder = Decoder::new(&[1, 2, 3]);
+
+        assert_eq!(decoder.skip(2), Ok(()));
+        assert_eq!(decoder.remaining(), &[3]);
+        assert!(decoder.skip(2).is_err());
+    }
+
+    #[test]
+    fn test_limited() {
+        let mut decoder = Decoder::new(&[1, 2, 3, 4]);
+
+        let res = decoder.limited(3, |d| d.read_array::<1>());
+
+        assert_eq!(res, Ok([1]));
+        assert_eq!(decoder.remaining(), &[4]);
+        assert_eq!(
+            decoder.limited(1, |d| d.read_array::<2>()),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 2,
+                available: 1
+            })
+        );
+        assert!(decoder.limited(2, |_| Ok(())).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_limited_reader() {
+        let mut input: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut decoder = Decoder::from_reader(&mut input);
+
+        let res = decoder.limited(4, |d| {
+            let inner = d.limited(2, |d| d.read_array::<1>())?;
+            let outer = d.read_array::<1>()?;
+
+            assert!(d.read_array::<2>().is_err());
+
+            Ok((inner, outer))
+        });
+
+        assert_eq!(res, Ok(([1], [3])));
+        assert_eq!(decoder.read_array::<2>(), Ok([5, 6]));
+    }
+}
@@ -4,13 +4,42 @@ use core::any::{Any, TypeId};
 #[cfg(all(feature = "std", feature = "bool_arr_optimization"))]
 use std::any::{Any, TypeId};
 #[cfg(feature = "std")]
-use std::collections::HashMap;
+use std::borrow::Cow;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+#[cfg(feature = "std")]
+use std::hash::BuildHasher;
+#[cfg(feature = "std")]
+use std::rc::Rc;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 
-#[cfg(not(feature = "std"))]
+use core::cell::{Cell, RefCell};
+use core::cmp::Reverse;
+use core::marker::PhantomData;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping,
+};
+
+use crate::{ByteSink, CountingSink, EncodeError, Encoder, Endian};
+
+#[cfg(feature = "alloc")]
+use crate::Config;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::borrow::{Cow, ToOwned};
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::boxed::Box;
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::rc::Rc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::string::String;
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::vec::Vec;
 
 /// Provide methods to encode objects into a vector of bytes.
@@ -21,8 +50,16 @@ use alloc::vec::Vec;
 /// provided for some common types but for custom types or other types the trait can be implemented
 /// using the below instructions.
 ///
+/// Both methods use the default [Config]. The `encoded_with_config` method, or an [Encoder]
+/// created with a different configuration, can be used to change the byte order, the width of
+/// length prefixes or the width of enum tags. Values can be written to any [ByteSink], such as
+/// a `std::io::Write`, using the `encode_to_sink` method or an [Encoder], or to a `&mut [u8]`
+/// without allocating using the `encode_to_slice` method.
+///
 /// ## Example
 /// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
 /// use byte_coding::Encodable;
 ///
 /// let data = Some("test");
@@ -34,17 +71,19 @@ use alloc::vec::Vec;
 /// let mut buf = Vec::new();
 /// data.encode_to_buf(&mut buf);
 /// assert_eq!(buf, vec![1, 4, 0, 0, 0, 0, 0, 0, 0, b't', b'e', b's', b't']);
+/// # }
 /// ```
 ///
 /// # Implementing
-/// When implementing this trait, simply implement the `encode_to_buf` method. Any data should be
-/// appended to the buffer provided as an argument. Where possible utilise the existing methods
-/// provided for other types.
+/// When implementing this trait, simply implement the `encode_to` method. Any data should be
+/// written to the [Encoder] provided as an argument and any error it returns should be passed on.
+/// Where possible utilise the existing methods provided for other types, so that the encoder's
+/// configuration is respected.
 ///
 /// ## Example
 /// The below example is an implementation of the trait for an example struct.
 /// ```
-/// use byte_coding::Encodable;
+/// use byte_coding::{Encodable, EncodeError, Encoder};
 ///
 /// struct Example {
 ///     f1: String,
@@ -52,16 +91,20 @@ use alloc::vec::Vec;
 /// }
 ///
 /// impl Encodable for Example {
-///     fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-///         self.f1.encode_to_buf(buf);
-///         self.f2.encode_to_buf(buf);
+///     fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+///         self.f1.encode_to(encoder)?;
+///         self.f2.encode_to(encoder)?;
+///
+///         return Ok(());
 ///     }
 /// }
 /// ```
 ///
 /// You can then encode the object like any other type
 /// ```
-/// # use byte_coding::Encodable;
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// # use byte_coding::{Encodable, EncodeError, Encoder};
 /// #
 /// # struct Example {
 /// #     f1: String,
@@ -69,9 +112,11 @@ use alloc::vec::Vec;
 /// # }
 /// #
 /// # impl Encodable for Example {
-/// #     fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-/// #         self.f1.encode_to_buf(buf);
-/// #         self.f2.encode_to_buf(buf);
+/// #     fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+/// #         self.f1.encode_to(encoder)?;
+/// #         self.f2.encode_to(encoder)?;
+/// #
+/// #         return Ok(());
 /// #     }
 /// # }
 /// #
@@ -81,14 +126,23 @@ use alloc::vec::Vec;
 /// };
 ///
 /// assert_eq!(example.encoded(), vec![7, 0, 0, 0, 0, 0, 0, 0, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 255, 255]);
+/// # }
 /// ```
-
-pub trait Encodable
-where
-    Self: Sized,
-{
+///
+/// ## Migrating from `encode_to_buf`
+/// Older versions of this trait required the `encode_to_buf` method. `encode_to` is now the
+/// required method, since an implementation of `encode_to_buf` could not pass the encoder's
+/// configuration on to the values it encodes. `encode_to_buf` is still provided, on top of
+/// `encode_to`, so existing callers continue to compile.
+pub trait Encodable {
     /// Returns a vector of bytes representing this object.
     ///
+    /// # Panics
+    /// Panics if an implementation returns an error. The implementations provided by this crate
+    /// never fail when using the default configuration. A derived implementation returns
+    /// [EncodeError::LengthFieldOverflow] if the length of a field with a `count` or `byte_len`
+    /// doesn't fit in its length field, use `encoded_with_config` to handle it.
+    ///
     /// ### Example
     /// ```
     /// use byte_coding::Encodable;
@@ -96,6 +150,7 @@ where
     /// let encoded = 65535u16.encoded();
     /// assert_eq!(vec![255, 255], encoded);
     /// ```
+    #[cfg(feature = "alloc")]
     fn encoded(&self) -> Vec<u8> {
         let mut buf = Vec::new();
 
@@ -104,7 +159,34 @@ where
         return buf;
     }
 
-    /// Append the bytes to the provided buffer which represent this object.
+    /// Returns a vector of bytes representing this object, encoded using the specified
+    /// configuration. If the object cannot be encoded using the configuration an [EncodeError]
+    /// is returned instead.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::{Config, Encodable, Endian};
+    ///
+    /// let encoded = 258u16.encoded_with_config(Config::new().with_endian(Endian::Big));
+    /// assert_eq!(Ok(vec![1, 2]), encoded);
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn encoded_with_config(&self, config: Config) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::new();
+
+        self.encode_to(&mut Encoder::with_config(&mut buf, config))?;
+
+        return Ok(buf);
+    }
+
+    /// Append the bytes to the provided buffer which represent this object, using the default
+    /// configuration.
+    ///
+    /// # Panics
+    /// Panics if an implementation returns an error. The implementations provided by this crate
+    /// never fail when using the default configuration. A derived implementation returns
+    /// [EncodeError::LengthFieldOverflow] if the length of a field with a `count` or `byte_len`
+    /// doesn't fit in its length field, use `encode_to_sink` to handle it.
     ///
     /// ### Example
     /// ```
@@ -116,137 +198,294 @@ where
     ///
     /// assert_eq!(vec![0, 0, 255, 255], encoded);
     /// ```
-    fn encode_to_buf(&self, buf: &mut Vec<u8>);
+    #[cfg(feature = "alloc")]
+    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
+        if let Err(e) = self.encode_to(&mut Encoder::new(buf)) {
+            panic!("encoding into a Vec failed: {}", e);
+        }
+    }
+
+    /// Writes the bytes which represent this object to the sink, using the default
+    /// configuration. If the sink cannot accept the bytes an [EncodeError] is returned instead.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::{CountingSink, Encodable};
+    ///
+    /// let mut sink = CountingSink::new();
+    ///
+    /// "abc".encode_to_sink(&mut sink).unwrap();
+    /// assert_eq!(sink.count(), 11);
+    /// ```
+    fn encode_to_sink(&self, sink: &mut dyn ByteSink) -> Result<(), EncodeError> {
+        return self.encode_to(&mut Encoder::new(sink));
+    }
+
+    /// Writes the bytes which represent this object to the start of the slice, using the default
+    /// configuration, and returns the number of bytes written. If the slice is too small
+    /// [EncodeError::BufferFull] is returned instead. This does not allocate.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::{Encodable, EncodeError};
+    ///
+    /// let mut buf = [0u8; 4];
+    ///
+    /// assert_eq!((1u8, 2u16).encode_to_slice(&mut buf), Ok(3));
+    /// assert_eq!(buf, [1, 2, 0, 0]);
+    /// assert_eq!(
+    ///     5u64.encode_to_slice(&mut buf),
+    ///     Err(EncodeError::BufferFull { needed: 8, available: 4 })
+    /// );
+    /// ```
+    fn encode_to_slice(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let len = buf.len();
+        let mut sink = buf;
+
+        self.encode_to_sink(&mut sink)?;
+
+        return Ok(len - sink.len());
+    }
+
+    /// Returns the number of bytes the `encoded` method returns for this object. The bytes are
+    /// counted rather than stored, so this does not allocate unless the implementation only
+    /// provides `encode_to_buf`. Types which always have the same length also implement
+    /// [FixedSize](crate::FixedSize).
+    ///
+    /// # Panics
+    /// Panics if an implementation returns an error. The implementations provided by this crate
+    /// never fail when using the default configuration. A derived implementation returns
+    /// [EncodeError::LengthFieldOverflow] if the length of a field with a `count` or `byte_len`
+    /// doesn't fit in its length field, use `encode_to_sink` with a [CountingSink](crate::CountingSink) to handle it.
+    ///
+    /// ### Example
+    /// ```
+    /// use byte_coding::Encodable;
+    ///
+    /// assert_eq!((1u8, "abc").encoded_len(), 12);
+    /// ```
+    fn encoded_len(&self) -> usize {
+        let mut sink = CountingSink::new();
+
+        if let Err(e) = self.encode_to_sink(&mut sink) {
+            panic!("encoding into a CountingSink failed: {}", e);
+        }
+
+        return sink.count();
+    }
+
+    /// Writes the bytes which represent this object to the encoder.
+    ///
+    /// ### Example
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use byte_coding::{Encodable, Encoder};
+    ///
+    /// let mut encoded = Vec::new();
+    /// let mut encoder = Encoder::new(&mut encoded);
+    ///
+    /// 1u8.encode_to(&mut encoder).unwrap();
+    /// "a".encode_to(&mut encoder).unwrap();
+    ///
+    /// assert_eq!(vec![1, 1, 0, 0, 0, 0, 0, 0, 0, b'a'], encoded);
+    /// # }
+    /// ```
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError>;
 }
 
 impl<T: Encodable> Encodable for Option<T> {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
         match self {
             Some(s) => {
-                1u8.encode_to_buf(buf);
-                s.encode_to_buf(buf);
+                1u8.encode_to(encoder)?;
+                s.encode_to(encoder)?;
             }
-            None => 0u8.encode_to_buf(buf),
+            None => 0u8.encode_to(encoder)?,
         }
+
+        return Ok(());
     }
 }
 
-impl Encodable for &str {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.len().encode_to_buf(buf);
-        buf.extend_from_slice(self.as_bytes());
+/// Results are encoded using a single byte tag, `0` for `Ok` and `1` for `Err`, followed by the
+/// contained value.
+impl<T: Encodable, E: Encodable> Encodable for Result<T, E> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        match self {
+            Ok(v) => {
+                0u8.encode_to(encoder)?;
+                v.encode_to(encoder)?;
+            }
+            Err(e) => {
+                1u8.encode_to(encoder)?;
+                e.encode_to(encoder)?;
+            }
+        }
+
+        return Ok(());
     }
 }
 
-impl Encodable for String {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.as_str().encode_to_buf(buf);
+impl Encodable for str {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        encoder.write_length(self.len())?;
+        encoder.write_bytes(self.as_bytes())?;
+
+        return Ok(());
     }
 }
 
-impl Encodable for u8 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+#[cfg(feature = "alloc")]
+impl Encodable for String {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_str().encode_to(encoder);
     }
 }
 
-impl Encodable for u16 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+/// Integers are encoded using their fixed width in the configured byte order, which is little
+/// endian by default.
+macro_rules! impl_encodable_int {
+    ($($ty:ident),+) => {
+        $(
+            impl Encodable for $ty {
+                fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+                    return match encoder.config().endian() {
+                        Endian::Little => encoder.write_bytes(&self.to_le_bytes()),
+                        Endian::Big => encoder.write_bytes(&self.to_be_bytes()),
+                    };
+                }
+            }
+        )+
+    };
+}
+
+impl_encodable_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Floats are encoded as their IEEE-754 bit pattern in the configured byte order. The bit pattern is
+/// preserved exactly, so negative zero, infinities and the sign and payload of NaN values all
+/// round trip unchanged.
+impl Encodable for f32 {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.to_bits().encode_to(encoder);
     }
 }
-impl Encodable for u32 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+
+/// Floats are encoded as their IEEE-754 bit pattern in the configured byte order. The bit pattern is
+/// preserved exactly, so negative zero, infinities and the sign and payload of NaN values all
+/// round trip unchanged.
+impl Encodable for f64 {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.to_bits().encode_to(encoder);
     }
 }
 
-impl Encodable for u64 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+impl Encodable for usize {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return (*self as u64).encode_to(encoder);
     }
 }
 
-impl Encodable for u128 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+impl Encodable for isize {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return (*self as i64).encode_to(encoder);
     }
 }
 
-impl Encodable for i8 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+#[cfg(feature = "alloc")]
+impl<T: Encodable + ?Sized> Encodable for Box<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_ref().encode_to(encoder);
     }
 }
 
-impl Encodable for i16 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+#[cfg(feature = "alloc")]
+impl<T: Encodable + ?Sized> Encodable for Rc<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_ref().encode_to(encoder);
     }
 }
 
-impl Encodable for i32 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+#[cfg(feature = "alloc")]
+impl<T: Encodable + ?Sized> Encodable for Arc<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_ref().encode_to(encoder);
     }
 }
 
-impl Encodable for i64 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+/// Borrowed and owned values are encoded identically.
+#[cfg(feature = "alloc")]
+impl<B: Encodable + ToOwned + ?Sized> Encodable for Cow<'_, B> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_ref().encode_to(encoder);
     }
 }
 
-impl Encodable for i128 {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        buf.extend_from_slice(&self.to_le_bytes());
+impl<T: Encodable + Copy> Encodable for Cell<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.get().encode_to(encoder);
     }
 }
 
-impl Encodable for usize {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        (*self as u64).encode_to_buf(buf);
+/// The value is immutably borrowed while it is encoded.
+///
+/// # Panics
+/// Panics if the value is currently mutably borrowed.
+impl<T: Encodable + ?Sized> Encodable for RefCell<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.borrow().encode_to(encoder);
     }
 }
 
-impl Encodable for isize {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        (*self as i64).encode_to_buf(buf);
+/// The mutex is locked while the value is encoded. A poisoned mutex is still encoded, as
+/// encoding cannot leave the value in an inconsistent state.
+#[cfg(feature = "std")]
+impl<T: Encodable + ?Sized> Encodable for Mutex<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        let guard = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        return guard.encode_to(encoder);
     }
 }
 
-impl<T: Encodable> Encodable for Box<T> {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.as_ref().encode_to_buf(buf);
+/// A read lock is held while the value is encoded. A poisoned lock is still encoded, as
+/// encoding cannot leave the value in an inconsistent state.
+#[cfg(feature = "std")]
+impl<T: Encodable + ?Sized> Encodable for RwLock<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        let guard = self.read().unwrap_or_else(PoisonError::into_inner);
+
+        return guard.encode_to(encoder);
     }
 }
 
-#[cfg(feature = "bool_arr_optimization")]
+#[cfg(all(feature = "alloc", feature = "bool_arr_optimization"))]
 impl<T: Encodable + Any> Encodable for Vec<T> {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.as_slice().encode_to_buf(buf);
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_slice().encode_to(encoder);
     }
 }
 
-#[cfg(not(feature = "bool_arr_optimization"))]
+#[cfg(all(feature = "alloc", not(feature = "bool_arr_optimization")))]
 impl<T: Encodable> Encodable for Vec<T> {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.as_slice().encode_to_buf(buf);
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_slice().encode_to(encoder);
     }
 }
 
 #[cfg(feature = "bool_arr_optimization")]
-impl<T: Encodable + Any> Encodable for &[T] {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.len().encode_to_buf(buf);
+impl<T: Encodable + Any> Encodable for [T] {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        encoder.write_length(self.len())?;
 
         if TypeId::of::<T>() == TypeId::of::<bool>() {
             // Optimized boolean storage
             let mut byte: u8 = 0b0000_0000;
 
+            #[allow(clippy::needless_range_loop)]
             for i in 0..self.len() {
                 if i % 8 == 0 && i != 0 {
-                    byte.encode_to_buf(buf);
+                    byte.encode_to(encoder)?;
                     byte = 0;
                 }
 
@@ -257,44 +496,49 @@ impl<T: Encodable + Any> Encodable for &[T] {
                 }
             }
 
-            byte.encode_to_buf(buf);
+            byte.encode_to(encoder)?;
         } else {
             for item in self.iter() {
-                item.encode_to_buf(buf);
+                item.encode_to(encoder)?;
             }
         }
+
+        return Ok(());
     }
 }
 
 #[cfg(not(feature = "bool_arr_optimization"))]
-impl<T: Encodable> Encodable for &[T] {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.len().encode_to_buf(buf);
+impl<T: Encodable> Encodable for [T] {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        encoder.write_length(self.len())?;
 
         for item in self.iter() {
-            item.encode_to_buf(buf);
+            item.encode_to(encoder)?;
         }
+
+        return Ok(());
     }
 }
 
 impl Encodable for bool {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
         let v: u8 = if *self { 1 } else { 0 };
 
-        v.encode_to_buf(buf);
+        return v.encode_to(encoder);
     }
 }
 
 #[cfg(feature = "bool_arr_optimization")]
 impl<T: Encodable + Any, const N: usize> Encodable for [T; N] {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
         if TypeId::of::<T>() == TypeId::of::<bool>() {
             // Optimized boolean storage
             let mut byte: u8 = 0b0000_0000;
 
+            #[allow(clippy::needless_range_loop)]
             for i in 0..self.len() {
                 if i % 8 == 0 && i != 0 {
-                    byte.encode_to_buf(buf);
+                    byte.encode_to(encoder)?;
                     byte = 0;
                 }
 
@@ -305,45 +549,202 @@ impl<T: Encodable + Any, const N: usize> Encodable for [T; N] {
                 }
             }
 
-            byte.encode_to_buf(buf);
+            byte.encode_to(encoder)?;
         } else {
             for item in self.iter() {
-                item.encode_to_buf(buf);
+                item.encode_to(encoder)?;
             }
         }
+
+        return Ok(());
     }
 }
 
 #[cfg(not(feature = "bool_arr_optimization"))]
 impl<T: Encodable, const N: usize> Encodable for [T; N] {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
         for item in self.iter() {
-            item.encode_to_buf(buf);
+            item.encode_to(encoder)?;
         }
+
+        return Ok(());
     }
 }
 
-impl<T: Encodable> Encodable for &T {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        (*self).encode_to_buf(buf);
+impl<T: Encodable + ?Sized> Encodable for &T {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return (*self).encode_to(encoder);
     }
 }
 
+/// A char is encoded as its unicode scalar value using a `u32`.
+impl Encodable for char {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return (*self as u32).encode_to(encoder);
+    }
+}
+
+/// Non-zero integers are encoded in the same way as the integer type they wrap.
+macro_rules! impl_encodable_non_zero {
+    ($($ty:ty),+) => {
+        $(
+            impl Encodable for $ty {
+                fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+                    return self.get().encode_to(encoder);
+                }
+            }
+        )+
+    };
+}
+
+impl_encodable_non_zero!(
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroUsize,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128,
+    NonZeroIsize
+);
+
+/// `PhantomData` is encoded as zero bytes.
+impl<T: ?Sized> Encodable for PhantomData<T> {
+    fn encode_to(&self, _encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return Ok(());
+    }
+}
+
+impl<T: Encodable> Encodable for Wrapping<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.0.encode_to(encoder);
+    }
+}
+
+impl<T: Encodable> Encodable for Saturating<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.0.encode_to(encoder);
+    }
+}
+
+impl<T: Encodable> Encodable for Reverse<T> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.0.encode_to(encoder);
+    }
+}
+
+/// The unit type is encoded as zero bytes.
+impl Encodable for () {
+    fn encode_to(&self, _encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return Ok(());
+    }
+}
+
+/// Tuples are encoded as each of their fields in order, without any additional data.
+macro_rules! impl_encodable_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Encodable),+> Encodable for ($($name,)+) {
+            fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+                $(self.$index.encode_to(encoder)?;)+
+
+                return Ok(());
+            }
+        }
+    };
+}
+
+impl_encodable_tuple!(A 0);
+impl_encodable_tuple!(A 0, B 1);
+impl_encodable_tuple!(A 0, B 1, C 2);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12, N 13);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12, N 13, O 14);
+impl_encodable_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11, M 12, N 13, O 14, P 15);
+
 #[cfg(feature = "std")]
-impl<K: Encodable, V: Encodable> Encodable for HashMap<K, V> {
-    fn encode_to_buf(&self, buf: &mut Vec<u8>) {
-        self.len().encode_to_buf(buf);
+impl<K: Encodable, V: Encodable, S: BuildHasher> Encodable for HashMap<K, V, S> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        encoder.write_length(self.len())?;
 
         for (k, v) in self {
-            k.encode_to_buf(buf);
-            v.encode_to_buf(buf);
+            k.encode_to(encoder)?;
+            v.encode_to(encoder)?;
         }
+
+        return Ok(());
     }
 }
 
-#[cfg(test)]
+#[cfg(feature = "std")]
+impl<T: Encodable, S: BuildHasher> Encodable for HashSet<T, S> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        encoder.write_length(self.len())?;
+
+        for item in self {
+            item.encode_to(encoder)?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Maps are encoded as the number of entries followed by each key and value pair, in the same
+/// format as a `HashMap`.
+#[cfg(feature = "alloc")]
+impl<K: Encodable, V: Encodable> Encodable for BTreeMap<K, V> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        encoder.write_length(self.len())?;
+
+        for (k, v) in self {
+            k.encode_to(encoder)?;
+            v.encode_to(encoder)?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// The remaining collections are encoded as the number of items followed by each item in
+/// iteration order. Unlike `Vec<bool>`, collections of booleans are not packed.
+#[cfg(feature = "alloc")]
+macro_rules! impl_encodable_sequence {
+    ($($collection:ident),+) => {
+        $(
+            impl<T: Encodable> Encodable for $collection<T> {
+                fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+                    encoder.write_length(self.len())?;
+
+                    for item in self {
+                        item.encode_to(encoder)?;
+                    }
+
+                    return Ok(());
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_encodable_sequence!(BTreeSet, VecDeque, BinaryHeap, LinkedList);
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
+    use crate::IntEncoding;
 
     #[cfg(not(feature = "std"))]
     use alloc::vec;
@@ -434,6 +835,12 @@ mod tests {
         assert_eq!(out, expected);
     }
 
+    #[test]
+    fn test_encoding_result() {
+        assert_eq!(Ok::<u8, u16>(5).encoded(), vec![0, 5]);
+        assert_eq!(Err::<u8, u16>(6).encoded(), vec![1, 6, 0]);
+    }
+
     #[test]
     fn test_encoding_u64() {
         assert_eq!(52u64.encoded(), vec![52, 0, 0, 0, 0, 0, 0, 0]);
@@ -447,6 +854,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encoding_f32() {
+        assert_eq!(1.5f32.encoded(), vec![0, 0, 0xc0, 0x3f]);
+        assert_eq!((-0.0f32).encoded(), vec![0, 0, 0, 0x80]);
+    }
+
+    #[test]
+    fn test_encoding_f64() {
+        assert_eq!(1.5f64.encoded(), vec![0, 0, 0, 0, 0, 0, 0xf8, 0x3f]);
+        assert_eq!(
+            f64::NEG_INFINITY.encoded(),
+            vec![0, 0, 0, 0, 0, 0, 0xf0, 0xff]
+        );
+    }
+
     #[test]
     fn test_encoding_vec_u64() {
         assert_eq!(
@@ -466,6 +888,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encoding_char() {
+        assert_eq!('a'.encoded(), vec![b'a', 0, 0, 0]);
+        assert_eq!('\u{1F980}'.encoded(), vec![0x80, 0xf9, 1, 0]);
+    }
+
+    #[test]
+    fn test_encoding_non_zero() {
+        assert_eq!(NonZeroU16::new(258).unwrap().encoded(), vec![2, 1]);
+        assert_eq!(NonZeroI8::new(-1).unwrap().encoded(), vec![255]);
+    }
+
+    #[test]
+    fn test_encoding_wrappers() {
+        assert_eq!(PhantomData::<String>.encoded(), Vec::<u8>::new());
+        assert_eq!(Wrapping(5u16).encoded(), vec![5, 0]);
+        assert_eq!(Saturating(5u16).encoded(), vec![5, 0]);
+        assert_eq!(Reverse(5u16).encoded(), vec![5, 0]);
+    }
+
+    #[test]
+    fn test_encoding_unit() {
+        assert_eq!(().encoded(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_encoding_tuple() {
+        assert_eq!(
+            (1u8, "a", 2u16).encoded(),
+            vec![1, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 2, 0]
+        );
+    }
+
+    #[test]
+    fn test_encoding_btree_map() {
+        let map = BTreeMap::from([(2u8, 20u16), (1u8, 10u16)]);
+
+        assert_eq!(
+            map.encoded(),
+            vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 10, 0, 2, 20, 0]
+        );
+    }
+
+    #[test]
+    fn test_encoding_sequences() {
+        let expected = vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 2];
+
+        assert_eq!(BTreeSet::from([2u8, 1]).encoded(), expected);
+        assert_eq!(VecDeque::from([1u8, 2]).encoded(), expected);
+        assert_eq!(LinkedList::from([1u8, 2]).encoded(), expected);
+        assert_eq!(
+            BinaryHeap::from([7u8]).encoded(),
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 7]
+        );
+    }
+
+    #[test]
+    fn test_encoding_pointers() {
+        let expected = vec![1, 0, 0, 0, 0, 0, 0, 0, b'a'];
+
+        assert_eq!(Box::<str>::from("a").encoded(), expected);
+        assert_eq!(Rc::<str>::from("a").encoded(), expected);
+        assert_eq!(Arc::<str>::from("a").encoded(), expected);
+        assert_eq!(Cow::Borrowed("a").encoded(), expected);
+        assert_eq!(Cow::<str>::Owned("a".into()).encoded(), expected);
+        assert_eq!(Rc::<[u8]>::from([b'a'].as_slice()).encoded(), expected);
+        assert_eq!(Box::<[u8]>::from([b'a'].as_slice()).encoded(), expected);
+    }
+
+    #[test]
+    fn test_encoding_cells() {
+        assert_eq!(Cell::new(3u8).encoded(), vec![3]);
+        assert_eq!(RefCell::new(3u8).encoded(), vec![3]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_encoding_locks() {
+        let mutex = Mutex::new(3u8);
+
+        let _ = std::panic::catch_unwind(|| {
+            let _guard = mutex.lock().unwrap();
+            panic!("poison the mutex");
+        });
+
+        assert!(mutex.is_poisoned());
+        assert_eq!(mutex.encoded(), vec![3]);
+        assert_eq!(RwLock::new(3u8).encoded(), vec![3]);
+    }
+
     #[test]
     fn test_encoding_str() {
         assert_eq!(
@@ -473,4 +985,46 @@ mod tests {
             vec![4, 0, 0, 0, 0, 0, 0, 0, b't', b'e', b's', b't']
         );
     }
+
+    #[test]
+    fn test_encoding_big_endian() {
+        let config = Config::new().with_endian(Endian::Big);
+
+        assert_eq!(0x0102u16.encoded_with_config(config).unwrap(), vec![1, 2]);
+        assert_eq!(
+            (-2i32).encoded_with_config(config).unwrap(),
+            vec![255, 255, 255, 254]
+        );
+        assert_eq!(
+            1.5f32.encoded_with_config(config).unwrap(),
+            vec![0x3f, 0xc0, 0, 0]
+        );
+        assert_eq!(
+            "a".encoded_with_config(config).unwrap(),
+            vec![0, 0, 0, 0, 0, 0, 0, 1, b'a']
+        );
+    }
+
+    #[test]
+    fn test_encoding_length_encoding() {
+        let config = Config::new().with_length_encoding(IntEncoding::U16);
+
+        assert_eq!(
+            vec![1u8, 2].encoded_with_config(config).unwrap(),
+            vec![2, 0, 1, 2]
+        );
+
+        let config = Config::new().with_length_encoding(IntEncoding::VarInt);
+        let mut expected = vec![0xac, 2];
+        expected.extend([7u8; 300]);
+
+        assert_eq!(
+            [7u8; 300].as_slice().encoded_with_config(config).unwrap(),
+            expected
+        );
+        assert_eq!(
+            BTreeSet::from([1u8]).encoded_with_config(config).unwrap(),
+            vec![1, 1]
+        );
+    }
 }
@@ -0,0 +1,289 @@
+use core::fmt::{Debug, Formatter};
+
+use crate::{ByteSink, Config, Encodable, EncodeError, Endian, IntEncoding, VarInt};
+
+#[cfg(not(feature = "alloc"))]
+use crate::CountingSink;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+
+/// The state of an encode operation, the output sink and the configuration being used.
+///
+/// An encoder is passed to [Encodable::encode_to](crate::Encodable::encode_to), which writes to
+/// it using the methods below. Any [ByteSink] can be used as the output.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Config, Encodable, Encoder, IntEncoding};
+///
+/// let mut buf = Vec::new();
+/// let mut encoder = Encoder::with_config(
+///     &mut buf,
+///     Config::new().with_length_encoding(IntEncoding::U8),
+/// );
+///
+/// "ab".encode_to(&mut encoder).unwrap();
+/// 1u16.encode_to(&mut encoder).unwrap();
+///
+/// assert_eq!(buf, vec![2, b'a', b'b', 1, 0]);
+/// # }
+/// ```
+pub struct Encoder<'a> {
+    sink: &'a mut dyn ByteSink,
+    config: Config,
+}
+
+impl<'a> Encoder<'a> {
+    /// Creates an encoder which writes to the sink using the default configuration.
+    pub fn new(sink: &'a mut dyn ByteSink) -> Self {
+        return Self::with_config(sink, Config::default());
+    }
+
+    /// Creates an encoder which writes to the sink using the specified configuration.
+    pub fn with_config(sink: &'a mut dyn ByteSink, config: Config) -> Self {
+        return Self { sink, config };
+    }
+
+    /// Returns the configuration used by this encoder.
+    pub fn config(&self) -> &Config {
+        return &self.config;
+    }
+
+    /// Writes the bytes to the sink.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        return self.sink.write_bytes(bytes);
+    }
+
+    /// Writes the length prefix of a collection or string using the configured length encoding.
+    /// If the length does not fit in the configured length encoding
+    /// [EncodeError::IntegerTooLarge] is returned.
+    pub fn write_length(&mut self, length: usize) -> Result<(), EncodeError> {
+        return self.write_uint(length as u64, self.config.length_encoding());
+    }
+
+    /// Writes the tag of an enum using the configured enum tag encoding. If the enum tag
+    /// encoding is [IntEncoding::U8] and the tag is larger than 255
+    /// [EncodeError::IntegerTooLarge] is returned.
+    pub fn write_enum_tag(&mut self, tag: u16) -> Result<(), EncodeError> {
+        return self.write_uint(tag as u64, self.config.enum_tag_encoding());
+    }
+
+    /// Writes a field of a tagged struct, which is the tag and the length of the value in bytes,
+    /// both as [VarInt]s, followed by the value. The length allows a decoder to skip tags it does
+    /// not know.
+    pub fn write_tagged<T: Encodable + ?Sized>(
+        &mut self,
+        tag: u32,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        return self.write_tagged_with(tag, |encoder| value.encode_to(encoder));
+    }
+
+    /// Writes a field of a tagged struct in the same way as `write_tagged`, where the value is
+    /// written by `f`. With the `alloc` feature `f` is called once, writing to a buffer which is
+    /// copied to the sink after the length.
+    #[cfg(feature = "alloc")]
+    pub fn write_tagged_with<F>(&mut self, tag: u32, f: F) -> Result<(), EncodeError>
+    where
+        F: Fn(&mut Encoder<'_>) -> Result<(), EncodeError>,
+    {
+        let mut buf = Vec::new();
+
+        f(&mut Encoder::with_config(&mut buf, self.config))?;
+
+        VarInt(tag).encode_to(self)?;
+        VarInt(buf.len()).encode_to(self)?;
+
+        return self.write_bytes(&buf);
+    }
+
+    /// Writes a field of a tagged struct in the same way as `write_tagged`, where the value is
+    /// written by `f`. Without the `alloc` feature `f` is called twice, first to count the length
+    /// of the value, so it must write the same bytes each time. A value which contains `n` nested
+    /// levels of tagged structs is therefore encoded `2^n` times.
+    #[cfg(not(feature = "alloc"))]
+    pub fn write_tagged_with<F>(&mut self, tag: u32, f: F) -> Result<(), EncodeError>
+    where
+        F: Fn(&mut Encoder<'_>) -> Result<(), EncodeError>,
+    {
+        let mut counter = CountingSink::new();
+
+        f(&mut Encoder::with_config(&mut counter, self.config))?;
+
+        VarInt(tag).encode_to(self)?;
+        VarInt(counter.count()).encode_to(self)?;
+
+        return f(self);
+    }
+
+    /// Writes `value` as an unsigned LEB128 variable length integer.
+    pub(crate) fn write_varint(&mut self, mut value: u128) -> Result<(), EncodeError> {
+        let mut bytes = [0u8; 19];
+        let mut len = 0;
+
+        while value >= 0x80 {
+            bytes[len] = (value as u8) | 0x80;
+            value >>= 7;
+            len += 1;
+        }
+
+        bytes[len] = value as u8;
+
+        return self.write_bytes(&bytes[..=len]);
+    }
+
+    fn write_uint(&mut self, value: u64, encoding: IntEncoding) -> Result<(), EncodeError> {
+        let max = match encoding {
+            IntEncoding::U8 => u8::MAX as u64,
+            IntEncoding::U16 => u16::MAX as u64,
+            IntEncoding::U32 => u32::MAX as u64,
+            IntEncoding::U64 | IntEncoding::VarInt => u64::MAX,
+        };
+
+        if value > max {
+            return Err(EncodeError::IntegerTooLarge { value, max });
+        }
+
+        let endian = self.config.endian();
+
+        return match encoding {
+            IntEncoding::U8 => self.write_bytes(&[value as u8]),
+            IntEncoding::U16 => match endian {
+                Endian::Little => self.write_bytes(&(value as u16).to_le_bytes()),
+                Endian::Big => self.write_bytes(&(value as u16).to_be_bytes()),
+            },
+            IntEncoding::U32 => match endian {
+                Endian::Little => self.write_bytes(&(value as u32).to_le_bytes()),
+                Endian::Big => self.write_bytes(&(value as u32).to_be_bytes()),
+            },
+            IntEncoding::U64 => match endian {
+                Endian::Little => self.write_bytes(&value.to_le_bytes()),
+                Endian::Big => self.write_bytes(&value.to_be_bytes()),
+            },
+            IntEncoding::VarInt => self.write_varint(value as u128),
+        };
+    }
+}
+
+impl Debug for Encoder<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        return f
+            .debug_struct("Encoder")
+            .field("config", &self.config)
+            .finish_non_exhaustive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[cfg(all(feature = "alloc", not(feature = "std")))]
+    use alloc::vec;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_write_varint() {
+        let mut buf = Vec::new();
+        let mut encoder = Encoder::new(&mut buf);
+
+        for v in [0, 127, 128, 300] {
+            encoder.write_varint(v).unwrap();
+        }
+
+        assert_eq!(buf, vec![0, 127, 0x80, 1, 0xac, 2]);
+
+        buf.clear();
+        Encoder::new(&mut buf)
+            .write_varint(u64::MAX as u128)
+            .unwrap();
+
+        assert_eq!(
+            buf,
+            vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 1]
+        );
+
+        buf.clear();
+        Encoder::new(&mut buf).write_varint(u128::MAX).unwrap();
+
+        assert_eq!(buf.len(), 19);
+        assert_eq!(buf[18], 3);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_write_length() {
+        let mut buf = Vec::new();
+        let config = Config::new()
+            .with_endian(Endian::Big)
+            .with_length_encoding(IntEncoding::U32);
+
+        Encoder::with_config(&mut buf, config)
+            .write_length(258)
+            .unwrap();
+
+        assert_eq!(buf, vec![0, 0, 1, 2]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_write_length_overflow() {
+        let mut buf = Vec::new();
+        let config = Config::new().with_length_encoding(IntEncoding::U8);
+
+        assert_eq!(
+            Encoder::with_config(&mut buf, config).write_length(256),
+            Err(EncodeError::IntegerTooLarge {
+                value: 256,
+                max: 255
+            })
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_write_tagged() {
+        let mut buf = Vec::new();
+
+        Encoder::new(&mut buf).write_tagged(300, "ab").unwrap();
+
+        assert_eq!(buf, vec![0xac, 2, 10, 2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']);
+    }
+
+    /// Writes the depth followed by the next level as a tagged field, counting the calls.
+    fn write_level(
+        encoder: &mut Encoder<'_>,
+        depth: u32,
+        calls: &Cell<usize>,
+    ) -> Result<(), EncodeError> {
+        calls.set(calls.get() + 1);
+        encoder.write_bytes(&[depth as u8])?;
+
+        if depth == 0 {
+            return Ok(());
+        }
+
+        return encoder.write_tagged_with(depth, |encoder| write_level(encoder, depth - 1, calls));
+    }
+
+    #[test]
+    fn test_write_tagged_nested() {
+        let calls = Cell::new(0);
+        let mut storage = [0u8; 10];
+
+        write_level(&mut Encoder::new(&mut &mut storage[..]), 3, &calls).unwrap();
+
+        assert_eq!(storage, [3, 3, 7, 2, 2, 4, 1, 1, 1, 0]);
+
+        // Without a buffer each level is encoded twice by the level above it
+        if cfg!(feature = "alloc") {
+            assert_eq!(calls.get(), 4);
+        } else {
+            assert_eq!(calls.get(), 1 + 2 * (1 + 2 * (1 + 2)));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use core::fmt::{Display, Formatter};
+
+/// The reasons a decode operation can fail.
+///
+/// Every [Decodable](crate::Decodable) implementation provided by this crate, as well as the code
+/// generated by the derive macros, reports failures using this type.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{Decodable, DecodeError};
+///
+/// let res = u32::decode(&[1, 2]);
+/// assert_eq!(res, Err(DecodeError::UnexpectedEnd { needed: 4, available: 2 }));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input ended before the value was complete. `needed` is the number of bytes the
+    /// current read required and `available` is the number of bytes that were left.
+    UnexpectedEnd { needed: usize, available: usize },
+    /// A string contained bytes which were not valid UTF-8. `valid_up_to` is the index of the
+    /// first invalid byte within the string.
+    InvalidUtf8 { valid_up_to: usize },
+    /// An enum tag (including the tag byte of an `Option`) did not match any known variant.
+    UnknownEnumTag { tag: u128 },
+    /// A `bool` was encoded using a byte other than 0 or 1.
+    InvalidBool { value: u8 },
+    /// A `char` was encoded using a value which is not a valid unicode scalar value.
+    InvalidChar { value: u32 },
+    /// A zero value was found when decoding one of the `NonZero` integer types.
+    InvalidNonZero,
+    /// A length prefix was too large to be represented on this platform.
+    LengthOverflow { length: u64 },
+    /// An `isize` value was too large to be represented on this platform.
+    IntegerOverflow { value: i64 },
+    /// A `usize` value was too large to be represented on this platform.
+    UnsignedIntegerOverflow { value: u64 },
+    /// A variable length integer was longer than necessary or too large for its type.
+    InvalidVarInt,
+    /// Decoding would allocate more than the allocation limit allows. `requested` is the size
+    /// of the allocation and `remaining` is what was left of the limit.
+    AllocationLimit { requested: usize, remaining: usize },
+    /// A collection or string was longer than the collection length limit allows.
+    CollectionTooLong { length: usize, max: usize },
+    /// Values were nested more deeply than the depth limit allows.
+    DepthLimit { max: usize },
+    /// A versioned struct was encoded with a newer version than the decoder supports, which
+    /// is `max`.
+    UnsupportedVersion { version: u32, max: u32 },
+    /// The field which stores the length of another field, using the `count` or `byte_len`
+    /// derive attributes, was negative or too large to be represented on this platform.
+    InvalidLengthField,
+    /// The elements of a field with a `byte_len` did not end exactly at the end of its bytes.
+    ByteLenMismatch,
+    /// A tagged struct contained the same tag more than once.
+    DuplicateTag { tag: u32 },
+    /// A `pre_dec_func` or `post_dec_func` hook signalled a failure.
+    HookFailed { hook: &'static str },
+    /// The value borrows from the input or uses decode hooks, so it can only be decoded from a
+    /// buffer and not from a reader.
+    RequiresBuffer,
+    /// Reading from a `std::io::Read` failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+    /// A failure reported by a custom implementation.
+    Custom(&'static str),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        return match self {
+            Self::UnexpectedEnd { needed, available } => write!(
+                f,
+                "unexpected end of input, needed {} bytes but only {} were available",
+                needed, available
+            ),
+            Self::InvalidUtf8 { valid_up_to } => {
+                write!(f, "invalid UTF-8 after {} bytes", valid_up_to)
+            }
+            Self::UnknownEnumTag { tag } => write!(f, "unknown enum tag: {}", tag),
+            Self::InvalidBool { value } => write!(f, "invalid bool value: {}", value),
+            Self::InvalidChar { value } => write!(f, "invalid char value: {:#x}", value),
+            Self::InvalidNonZero => f.write_str("zero value for a non-zero integer"),
+            Self::LengthOverflow { length } => {
+                write!(f, "length {} does not fit in a usize", length)
+            }
+            Self::IntegerOverflow { value } => {
+                write!(f, "value {} does not fit in an isize", value)
+            }
+            Self::UnsignedIntegerOverflow { value } => {
+                write!(f, "value {} does not fit in a usize", value)
+            }
+            Self::InvalidVarInt => f.write_str("invalid variable length integer"),
+            Self::AllocationLimit {
+                requested,
+                remaining,
+            } => write!(
+                f,
+                "allocation of {} bytes exceeds the remaining limit of {} bytes",
+                requested, remaining
+            ),
+            Self::CollectionTooLong { length, max } => write!(
+                f,
+                "collection length {} exceeds the limit of {}",
+                length, max
+            ),
+            Self::DepthLimit { max } => write!(f, "nesting depth exceeds the limit of {}", max),
+            Self::UnsupportedVersion { version, max } => write!(
+                f,
+                "version {} is newer than the supported version {}",
+                version, max
+            ),
+            Self::InvalidLengthField => f.write_str("invalid value for a length field"),
+            Self::ByteLenMismatch => f.write_str("the elements did not fill the byte length"),
+            Self::DuplicateTag { tag } => write!(f, "duplicate tag: {}", tag),
+            Self::HookFailed { hook } => write!(f, "decode hook '{}' failed", hook),
+            Self::RequiresBuffer => f.write_str("the value can only be decoded from a buffer"),
+            #[cfg(feature = "std")]
+            Self::Io(kind) => write!(f, "reading failed: {}", kind),
+            Self::Custom(msg) => f.write_str(msg),
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// The reasons an encode operation can fail.
+///
+/// Encoding into a `Vec<u8>` using the default [Config](crate::Config) cannot fail with the
+/// implementations provided by this crate. Other sinks may run out of space or report an IO
+/// error, and a configuration with a narrow length or enum tag encoding may be unable to
+/// represent a value.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Config, Encodable, EncodeError, IntEncoding};
+///
+/// let config = Config::new().with_length_encoding(IntEncoding::U8);
+/// let res = vec![0u8; 256].encoded_with_config(config);
+/// assert_eq!(res, Err(EncodeError::IntegerTooLarge { value: 256, max: 255 }));
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// The sink did not have enough space. `needed` is the number of bytes the current write
+    /// required and `available` is the number of bytes that were left.
+    BufferFull { needed: usize, available: usize },
+    /// A length prefix or enum tag was too large for the configured encoding, which can hold
+    /// at most `max`.
+    IntegerTooLarge { value: u64, max: u64 },
+    /// The length of a field was too large for the field which stores it, using the `count` or
+    /// `byte_len` derive attributes.
+    LengthFieldOverflow { length: usize },
+    /// The encoding of a framed value was longer than the maximum payload length of the
+    /// [FrameConfig](crate::FrameConfig).
+    #[cfg(feature = "framing")]
+    FrameTooLarge { length: usize, max: usize },
+    /// Writing to a `std::io::Write` failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+    /// A failure reported by a custom implementation.
+    Custom(&'static str),
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        return match self {
+            Self::BufferFull { needed, available } => write!(
+                f,
+                "buffer full, needed {} bytes but only {} were available",
+                needed, available
+            ),
+            Self::IntegerTooLarge { value, max } => write!(
+                f,
+                "value {} is larger than the configured maximum of {}",
+                value, max
+            ),
+            Self::LengthFieldOverflow { length } => {
+                write!(f, "length {} does not fit in its length field", length)
+            }
+            #[cfg(feature = "framing")]
+            Self::FrameTooLarge { length, max } => write!(
+                f,
+                "frame payload of {} bytes exceeds the maximum of {} bytes",
+                length, max
+            ),
+            #[cfg(feature = "std")]
+            Self::Io(kind) => write!(f, "IO error: {}", kind),
+            Self::Custom(msg) => f.write_str(msg),
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// The reasons a [FrameDecoder](crate::FrameDecoder) can fail to return the next frame.
+///
+/// Every error except [FrameError::Truncated] discards the invalid frame, so decoding can
+/// continue with the next one.
+#[cfg(feature = "framing")]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FrameError {
+    /// The frame did not start with the configured magic number.
+    InvalidMagic,
+    /// The length of the payload was larger than the configured maximum.
+    FrameTooLarge { length: usize, max: usize },
+    /// The checksum in the frame did not match the checksum of the received bytes.
+    ChecksumMismatch { expected: u32, actual: u32 },
+    /// The stream ended part way through a frame. `needed` is the number of bytes the frame
+    /// required and `available` is the number of bytes that were received.
+    Truncated { needed: usize, available: usize },
+    /// The payload was decoded but `count` bytes were left over.
+    TrailingBytes { count: usize },
+    /// The payload could not be decoded.
+    Decode(DecodeError),
+}
+
+#[cfg(feature = "framing")]
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        return match self {
+            Self::InvalidMagic => f.write_str("frame does not start with the magic number"),
+            Self::FrameTooLarge { length, max } => write!(
+                f,
+                "frame payload of {} bytes exceeds the maximum of {} bytes",
+                length, max
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "frame checksum {:#x} does not match the computed checksum {:#x}",
+                expected, actual
+            ),
+            Self::Truncated { needed, available } => write!(
+                f,
+                "truncated frame, needed {} bytes but only {} were received",
+                needed, available
+            ),
+            Self::TrailingBytes { count } => {
+                write!(f, "{} bytes left over after decoding the payload", count)
+            }
+            Self::Decode(e) => write!(f, "invalid payload: {}", e),
+        };
+    }
+}
+
+#[cfg(feature = "framing")]
+impl From<DecodeError> for FrameError {
+    fn from(value: DecodeError) -> Self {
+        return Self::Decode(value);
+    }
+}
+
+#[cfg(all(feature = "std", feature = "framing"))]
+impl std::error::Error for FrameError {}
+
+/// Converts the value returned by a `pre_dec_func` or `post_dec_func` hook into a `Result`.
+///
+/// This allows hooks to return either an `Option` or a `Result<_, DecodeError>`. It is used by
+/// the derive macros and is not intended to be used directly.
+#[doc(hidden)]
+pub trait IntoDecodeResult<T> {
+    fn into_decode_result(self, hook: &'static str) -> Result<T, DecodeError>;
+}
+
+impl<T> IntoDecodeResult<T> for Option<T> {
+    fn into_decode_result(self, hook: &'static str) -> Result<T, DecodeError> {
+        return self.ok_or(DecodeError::HookFailed { hook });
+    }
+}
+
+impl<T> IntoDecodeResult<T> for Result<T, DecodeError> {
+    fn into_decode_result(self, _hook: &'static str) -> Result<T, DecodeError> {
+        return self;
+    }
+}
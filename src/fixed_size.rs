@@ -0,0 +1,211 @@
+#[cfg(feature = "bool_arr_optimization")]
+use core::any::Any;
+use core::cmp::Reverse;
+use core::marker::PhantomData;
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping,
+};
+
+use crate::Encodable;
+
+/// Implemented by types whose encoding always has the same length, regardless of the value.
+///
+/// `SIZE` is the number of bytes written using the default [Config](crate::Config), it is
+/// equal to the value returned by the `encoded_len` method for every value of the type. Only the
+/// width of enum tags depends on the configuration, so a derived enum without an
+/// `encoding_type` has a different size when another tag width is configured. Since `SIZE` is a
+/// constant it can be used to size arrays, such as the buffer passed to `encode_to_slice`.
+///
+/// Implementations are provided for integers, floats, `bool`, `char`, arrays and tuples of fixed
+/// size types, and the types which are encoded in the same way as the type they wrap. Arrays of
+/// `bool` use the packed layout when the `bool_arr_optimization` feature is enabled. Types with
+/// a length prefix, such as `String`, `Vec` and `Option`, do not implement this trait, nor do
+/// [VarInt](crate::VarInt) and [ZigZag](crate::ZigZag).
+///
+/// The trait can be derived for structs and enums with only fixed size fields.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{Encodable, FixedSize};
+///
+/// assert_eq!(<(u8, [u16; 4])>::SIZE, 9);
+/// assert_eq!((1u8, [0u16; 4]).encoded_len(), 9);
+///
+/// let mut buf = [0u8; u32::SIZE];
+/// 7u32.encode_to_slice(&mut buf).unwrap();
+/// ```
+pub trait FixedSize: Encodable {
+    /// The number of bytes in the encoding of every value of this type.
+    const SIZE: usize;
+
+    /// Whether this type is `bool`, so arrays can determine whether they are packed.
+    #[doc(hidden)]
+    const IS_BOOL: bool = false;
+}
+
+macro_rules! impl_fixed_size {
+    ($($ty:ty => $size:expr),+) => {
+        $(
+            impl FixedSize for $ty {
+                const SIZE: usize = $size;
+            }
+        )+
+    };
+}
+
+impl_fixed_size!(
+    u8 => 1,
+    u16 => 2,
+    u32 => 4,
+    u64 => 8,
+    u128 => 16,
+    i8 => 1,
+    i16 => 2,
+    i32 => 4,
+    i64 => 8,
+    i128 => 16,
+    usize => 8,
+    isize => 8,
+    f32 => 4,
+    f64 => 8,
+    char => 4,
+    () => 0,
+    NonZeroU8 => 1,
+    NonZeroU16 => 2,
+    NonZeroU32 => 4,
+    NonZeroU64 => 8,
+    NonZeroU128 => 16,
+    NonZeroUsize => 8,
+    NonZeroI8 => 1,
+    NonZeroI16 => 2,
+    NonZeroI32 => 4,
+    NonZeroI64 => 8,
+    NonZeroI128 => 16,
+    NonZeroIsize => 8
+);
+
+impl FixedSize for bool {
+    const SIZE: usize = 1;
+    const IS_BOOL: bool = true;
+}
+
+impl<T: ?Sized> FixedSize for PhantomData<T> {
+    const SIZE: usize = 0;
+}
+
+impl<T: FixedSize + ?Sized> FixedSize for &T {
+    const SIZE: usize = T::SIZE;
+}
+
+impl<T: FixedSize> FixedSize for Wrapping<T> {
+    const SIZE: usize = T::SIZE;
+}
+
+impl<T: FixedSize> FixedSize for Saturating<T> {
+    const SIZE: usize = T::SIZE;
+}
+
+impl<T: FixedSize> FixedSize for Reverse<T> {
+    const SIZE: usize = T::SIZE;
+}
+
+/// Booleans are packed 8 to a byte, the packed encoding always writes at least one byte.
+#[cfg(feature = "bool_arr_optimization")]
+impl<T: FixedSize + Any, const N: usize> FixedSize for [T; N] {
+    const SIZE: usize = if !T::IS_BOOL {
+        T::SIZE * N
+    } else if N == 0 {
+        1
+    } else {
+        N.div_ceil(8)
+    };
+}
+
+#[cfg(not(feature = "bool_arr_optimization"))]
+impl<T: FixedSize, const N: usize> FixedSize for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+}
+
+macro_rules! impl_fixed_size_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: FixedSize),+> FixedSize for ($($name,)+) {
+            const SIZE: usize = 0 $(+ $name::SIZE)+;
+        }
+    };
+}
+
+impl_fixed_size_tuple!(A);
+impl_fixed_size_tuple!(A, B);
+impl_fixed_size_tuple!(A, B, C);
+impl_fixed_size_tuple!(A, B, C, D);
+impl_fixed_size_tuple!(A, B, C, D, E);
+impl_fixed_size_tuple!(A, B, C, D, E, F);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M, N);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
+impl_fixed_size_tuple!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_size<T: FixedSize>(value: T) {
+        assert_eq!(value.encoded_len(), T::SIZE);
+    }
+
+    #[test]
+    fn test_int_sizes() {
+        assert_size(1u8);
+        assert_size(-1i16);
+        assert_size(u32::MAX);
+        assert_size(1usize);
+        assert_size(-1isize);
+        assert_size(1u128);
+        assert_size(1.5f32);
+        assert_size(1.5f64);
+        assert_size('a');
+        assert_size(true);
+        assert_size(NonZeroU16::new(1).unwrap());
+        assert_size(Wrapping(1u64));
+        assert_size(Reverse(1i8));
+
+        assert_eq!(u16::SIZE, 2);
+        assert_eq!(NonZeroI128::SIZE, 16);
+        assert_eq!(<&u32>::SIZE, 4);
+    }
+
+    #[test]
+    fn test_compound_sizes() {
+        assert_size(());
+        assert_size(PhantomData::<u64>);
+        assert_size((1u8, 2u32, [3u16; 5]));
+        assert_size([[1u8; 3]; 4]);
+
+        assert_eq!(<(u8, u32, [u16; 5])>::SIZE, 15);
+        assert_eq!(<[[u8; 3]; 4]>::SIZE, 12);
+    }
+
+    #[test]
+    fn test_bool_array_sizes() {
+        assert_size([true; 0]);
+        assert_size([true; 1]);
+        assert_size([true; 8]);
+        assert_size([false; 9]);
+        assert_size([[true; 3]; 2]);
+
+        if cfg!(feature = "bool_arr_optimization") {
+            assert_eq!(<[bool; 9]>::SIZE, 2);
+            assert_eq!(<[[bool; 3]; 2]>::SIZE, 2);
+        } else {
+            assert_eq!(<[bool; 9]>::SIZE, 9);
+            assert_eq!(<[[bool; 3]; 2]>::SIZE, 6);
+        }
+    }
+}
@@ -0,0 +1,736 @@
+use crate::{ByteSink, Config, Decodable, Decoder, Encodable, EncodeError, Encoder, FrameError};
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+/// The checksum appended to each frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Checksum {
+    /// CRC-16/CCITT-FALSE, the polynomial 0x1021 with an initial value of 0xffff, written as a
+    /// `u16`.
+    Crc16,
+    /// CRC-32 as used by Ethernet and zlib, the reflected polynomial 0xedb88320, written as a
+    /// `u32`. This is the default.
+    Crc32,
+}
+
+impl Checksum {
+    /// Returns the number of bytes the checksum occupies in a frame.
+    pub const fn size(&self) -> usize {
+        return match self {
+            Self::Crc16 => 2,
+            Self::Crc32 => 4,
+        };
+    }
+
+    /// Computes the checksum of the bytes. A CRC-16 is returned in the low 16 bits.
+    ///
+    /// ## Example
+    /// ```
+    /// use byte_coding::Checksum;
+    ///
+    /// assert_eq!(Checksum::Crc16.compute(b"123456789"), 0x29b1);
+    /// assert_eq!(Checksum::Crc32.compute(b"123456789"), 0xcbf43926);
+    /// ```
+    pub fn compute(&self, bytes: &[u8]) -> u32 {
+        return match self {
+            Self::Crc16 => {
+                let mut crc: u16 = 0xffff;
+
+                for b in bytes {
+                    crc = (crc << 8) ^ CRC16_TABLE[((crc >> 8) as u8 ^ *b) as usize];
+                }
+
+                crc as u32
+            }
+            Self::Crc32 => {
+                let mut crc: u32 = 0xffff_ffff;
+
+                for b in bytes {
+                    crc = (crc >> 8) ^ CRC32_TABLE[(crc as u8 ^ *b) as usize];
+                }
+
+                !crc
+            }
+        };
+    }
+
+    /// Writes the checksum to the encoder using its configured byte order.
+    fn encode_to(&self, checksum: u32, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return match self {
+            Self::Crc16 => (checksum as u16).encode_to(encoder),
+            Self::Crc32 => checksum.encode_to(encoder),
+        };
+    }
+
+    /// Reads a checksum written by `encode_to`, the bytes must be exactly `size` long.
+    fn decode(&self, bytes: &[u8], config: Config) -> u32 {
+        let res = match self {
+            Self::Crc16 => u16::decode_with_config(bytes, config).map(u32::from),
+            Self::Crc32 => u32::decode_with_config(bytes, config),
+        };
+
+        let Ok(checksum) = res else {
+            unreachable!("the checksum bytes have the length of the checksum");
+        };
+
+        return checksum;
+    }
+}
+
+const CRC16_TABLE: [u16; 256] = crc16_table();
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc16_table() -> [u16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    return table;
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    return table;
+}
+
+/// The format of the frames written by a [FrameEncoder] and read by a [FrameDecoder].
+///
+/// Each frame contains the magic number, the length of the payload as a `u32`, the payload and
+/// the checksum of the length and payload. The length and checksum are written in the byte
+/// order of the [Config], which is also used to encode the payload. By default the magic number
+/// is `b"BC"`, a CRC-32 is used and payloads are limited to 16 MiB. Both ends of a connection
+/// must use the same configuration.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{Checksum, FrameConfig};
+///
+/// let config = FrameConfig::new()
+///     .with_magic(&[0xca, 0xfe])
+///     .with_checksum(Checksum::Crc16)
+///     .with_max_payload_len(1024);
+///
+/// assert_eq!(config.overhead(), 8);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FrameConfig {
+    magic: &'static [u8],
+    checksum: Checksum,
+    max_payload_len: usize,
+    config: Config,
+}
+
+impl FrameConfig {
+    /// Creates the default frame configuration.
+    pub const fn new() -> Self {
+        return Self {
+            magic: b"BC",
+            checksum: Checksum::Crc32,
+            max_payload_len: 16 * 1024 * 1024,
+            config: Config::new(),
+        };
+    }
+
+    /// Sets the bytes written at the start of each frame. The magic number can be empty, but
+    /// the decoder then has no way to find the start of the next frame after an error.
+    pub const fn with_magic(mut self, magic: &'static [u8]) -> Self {
+        self.magic = magic;
+
+        return self;
+    }
+
+    /// Sets the checksum appended to each frame.
+    pub const fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+
+        return self;
+    }
+
+    /// Sets the maximum length of a payload. Longer payloads are rejected when encoding, and
+    /// when decoding as soon as their length has been read. Lengths can be at most `u32::MAX`
+    /// regardless of this value.
+    pub const fn with_max_payload_len(mut self, max_payload_len: usize) -> Self {
+        self.max_payload_len = max_payload_len;
+
+        return self;
+    }
+
+    /// Sets the configuration used to encode the payload, and the byte order of the length and
+    /// checksum.
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+
+        return self;
+    }
+
+    /// Returns the bytes written at the start of each frame.
+    pub const fn magic(&self) -> &'static [u8] {
+        return self.magic;
+    }
+
+    /// Returns the checksum appended to each frame.
+    pub const fn checksum(&self) -> Checksum {
+        return self.checksum;
+    }
+
+    /// Returns the maximum length of a payload.
+    pub const fn max_payload_len(&self) -> usize {
+        return self.max_payload_len;
+    }
+
+    /// Returns the configuration used to encode the payload.
+    pub const fn config(&self) -> &Config {
+        return &self.config;
+    }
+
+    /// Returns the number of bytes each frame adds to its payload.
+    pub const fn overhead(&self) -> usize {
+        return self.header_len() + self.checksum.size();
+    }
+
+    /// The length of the magic number and the length prefix.
+    const fn header_len(&self) -> usize {
+        return self.magic.len() + 4;
+    }
+
+    /// The maximum payload length, which is limited by the `u32` length prefix.
+    fn max_len(&self) -> usize {
+        return self.max_payload_len.min(u32::MAX as usize);
+    }
+}
+
+impl Default for FrameConfig {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// Wraps encoded values in frames so they can be sent over a stream such as a TCP connection.
+/// See [FrameConfig] for the format of a frame.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{FrameConfig, FrameDecoder, FrameEncoder};
+///
+/// let encoder = FrameEncoder::new(FrameConfig::new());
+/// let mut decoder = FrameDecoder::new(FrameConfig::new());
+///
+/// let frame = encoder.encode(&(1u8, "message")).unwrap();
+/// assert_eq!(&frame[..7], &[b'B', b'C', 16, 0, 0, 0, 1]);
+///
+/// decoder.push(&frame);
+/// assert_eq!(decoder.next_frame(), Ok(Some((1u8, "message".to_string()))));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FrameEncoder {
+    config: FrameConfig,
+}
+
+impl FrameEncoder {
+    /// Creates an encoder which writes frames using the configuration.
+    pub fn new(config: FrameConfig) -> Self {
+        return Self { config };
+    }
+
+    /// Returns the configuration used to write frames.
+    pub fn config(&self) -> &FrameConfig {
+        return &self.config;
+    }
+
+    /// Returns a frame containing the value. If the value cannot be encoded or its encoding is
+    /// longer than the maximum payload length an [EncodeError] is returned instead.
+    pub fn encode<T: Encodable + ?Sized>(&self, value: &T) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::new();
+
+        self.encode_to_sink(value, &mut buf)?;
+
+        return Ok(buf);
+    }
+
+    /// Writes a frame containing the value to the sink. The payload is buffered so its length
+    /// can be written first. If the value cannot be encoded, its encoding is longer than the
+    /// maximum payload length or the sink cannot accept the frame an [EncodeError] is returned
+    /// instead.
+    pub fn encode_to_sink<T: Encodable + ?Sized>(
+        &self,
+        value: &T,
+        sink: &mut dyn ByteSink,
+    ) -> Result<(), EncodeError> {
+        // The length prefix is buffered with the payload so the checksum can cover both
+        let mut body = vec![0; 4];
+        value.encode_to(&mut Encoder::with_config(&mut body, self.config.config))?;
+
+        let length = body.len() - 4;
+
+        if length > self.config.max_len() {
+            return Err(EncodeError::FrameTooLarge {
+                length,
+                max: self.config.max_len(),
+            });
+        }
+
+        let mut prefix = &mut body[..4];
+        (length as u32).encode_to(&mut Encoder::with_config(&mut prefix, self.config.config))?;
+
+        let checksum = self.config.checksum.compute(&body);
+        let mut encoder = Encoder::with_config(sink, self.config.config);
+
+        encoder.write_bytes(self.config.magic)?;
+        encoder.write_bytes(&body)?;
+
+        return self.config.checksum.encode_to(checksum, &mut encoder);
+    }
+}
+
+/// Collects bytes received from a stream and yields the values in each complete frame. See
+/// [FrameConfig] for the format of a frame.
+///
+/// Bytes can be pushed in chunks of any size, a frame is returned once all of its bytes have
+/// been received. A frame with the wrong magic number, a length larger than the maximum payload
+/// length or an incorrect checksum is reported as a [FrameError], after which the decoder skips
+/// forward to the next occurrence of the magic number so later frames can still be decoded.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{FrameConfig, FrameDecoder, FrameEncoder, FrameError};
+///
+/// let encoder = FrameEncoder::new(FrameConfig::new());
+/// let mut decoder = FrameDecoder::new(FrameConfig::new());
+///
+/// let mut stream = encoder.encode(&1u16).unwrap();
+/// stream.extend(encoder.encode(&2u16).unwrap());
+///
+/// // Corrupt the first frame
+/// stream[6] ^= 0xff;
+///
+/// for chunk in stream.chunks(3) {
+///     decoder.push(chunk);
+/// }
+///
+/// assert!(matches!(
+///     decoder.next_frame::<u16>(),
+///     Err(FrameError::ChecksumMismatch { .. })
+/// ));
+/// assert_eq!(decoder.next_frame(), Ok(Some(2u16)));
+/// assert_eq!(decoder.next_frame::<u16>(), Ok(None));
+/// assert_eq!(decoder.finish(), Ok(()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FrameDecoder {
+    config: FrameConfig,
+    buffer: Vec<u8>,
+    /// The index of the first byte in the buffer which has not been consumed.
+    offset: usize,
+}
+
+impl FrameDecoder {
+    /// Creates a decoder which reads frames using the configuration.
+    pub fn new(config: FrameConfig) -> Self {
+        return Self {
+            config,
+            buffer: Vec::new(),
+            offset: 0,
+        };
+    }
+
+    /// Returns the configuration used to read frames.
+    pub fn config(&self) -> &FrameConfig {
+        return &self.config;
+    }
+
+    /// Appends bytes received from the stream.
+    pub fn push(&mut self, bytes: &[u8]) {
+        // Consumed bytes are only removed here, so reading many frames from the buffer does not
+        // move the remaining bytes once per frame
+        if self.offset != 0 {
+            self.buffer.drain(..self.offset);
+            self.offset = 0;
+        }
+
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Returns the number of bytes which have been received but not yet returned in a frame.
+    pub fn buffered_len(&self) -> usize {
+        return self.pending().len();
+    }
+
+    /// Decodes the value in the next frame. `Ok(None)` is returned if the frame has not been
+    /// completely received yet.
+    ///
+    /// If the frame is invalid a [FrameError] is returned and the decoder skips to the next
+    /// frame. If the frame is valid but the payload cannot be decoded as `T`, or it contains
+    /// bytes after the value, the frame is discarded and a [FrameError] is returned.
+    pub fn next_frame<T: Decodable>(&mut self) -> Result<Option<T>, FrameError> {
+        let Some(length) = self.next_frame_len()? else {
+            return Ok(None);
+        };
+
+        let start = self.config.header_len();
+        let payload = &self.pending()[start..start + length];
+        let mut decoder = Decoder::with_config(payload, self.config.config);
+        let res = T::decode_from(&mut decoder);
+        let trailing = decoder.remaining().len();
+
+        self.consume(length + self.config.overhead());
+
+        let value = res?;
+
+        if trailing != 0 {
+            return Err(FrameError::TrailingBytes { count: trailing });
+        }
+
+        return Ok(Some(value));
+    }
+
+    /// Returns the payload of the next frame without decoding it. `Ok(None)` is returned if the
+    /// frame has not been completely received yet. If the frame is invalid a [FrameError] is
+    /// returned and the decoder skips to the next frame.
+    pub fn next_payload(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let Some(length) = self.next_frame_len()? else {
+            return Ok(None);
+        };
+
+        let start = self.config.header_len();
+        let payload = self.pending()[start..start + length].to_vec();
+
+        self.consume(length + self.config.overhead());
+
+        return Ok(Some(payload));
+    }
+
+    /// Checks that the stream did not end part way through a frame. If bytes are still buffered
+    /// [FrameError::Truncated] is returned, `needed` is the number of bytes in the incomplete
+    /// frame, or in its header if the length has not been received.
+    pub fn finish(&self) -> Result<(), FrameError> {
+        if self.pending().is_empty() {
+            return Ok(());
+        }
+
+        let header_len = self.config.header_len();
+        let needed = if self.pending().len() < header_len {
+            header_len
+        } else {
+            self.read_length() + self.config.overhead()
+        };
+
+        return Err(FrameError::Truncated {
+            needed,
+            available: self.pending().len(),
+        });
+    }
+
+    /// Validates the frame at the start of the buffer and returns the length of its payload, or
+    /// `None` if the frame is incomplete. The frame is skipped if it is invalid.
+    fn next_frame_len(&mut self) -> Result<Option<usize>, FrameError> {
+        if !self.starts_with_magic(0) {
+            self.skip_frame();
+
+            return Err(FrameError::InvalidMagic);
+        }
+
+        if self.pending().len() < self.config.header_len() {
+            return Ok(None);
+        }
+
+        let length = self.read_length();
+
+        if length > self.config.max_len() {
+            self.skip_frame();
+
+            return Err(FrameError::FrameTooLarge {
+                length,
+                max: self.config.max_len(),
+            });
+        }
+
+        let body_end = self.config.header_len() + length;
+
+        let pending = self.pending();
+
+        if pending.len() < body_end + self.config.checksum.size() {
+            return Ok(None);
+        }
+
+        let checksum = self.config.checksum;
+        let expected = checksum.decode(
+            &pending[body_end..body_end + checksum.size()],
+            self.config.config,
+        );
+        let actual = checksum.compute(&pending[self.config.magic.len()..body_end]);
+
+        if expected != actual {
+            self.skip_frame();
+
+            return Err(FrameError::ChecksumMismatch { expected, actual });
+        }
+
+        return Ok(Some(length));
+    }
+
+    /// Reads the length prefix, the header must have been received.
+    fn read_length(&self) -> usize {
+        let start = self.config.magic.len();
+        let Ok(length) =
+            u32::decode_with_config(&self.pending()[start..start + 4], self.config.config)
+        else {
+            unreachable!("the header has been received");
+        };
+
+        return length as usize;
+    }
+
+    /// Returns the bytes which have been received but not yet consumed.
+    fn pending(&self) -> &[u8] {
+        return &self.buffer[self.offset..];
+    }
+
+    /// Marks the first `len` pending bytes as consumed. The buffer is compacted by `push`.
+    fn consume(&mut self, len: usize) {
+        self.offset += len;
+
+        if self.offset == self.buffer.len() {
+            self.buffer.clear();
+            self.offset = 0;
+        }
+    }
+
+    /// Whether the pending bytes at the index match the magic number, or the start of it if the
+    /// buffer ends first.
+    fn starts_with_magic(&self, index: usize) -> bool {
+        let pending = self.pending();
+        let magic = self.config.magic;
+        let available = (pending.len() - index).min(magic.len());
+
+        return pending[index..index + available] == magic[..available];
+    }
+
+    /// Discards the first pending byte and any bytes before the next magic number.
+    fn skip_frame(&mut self) {
+        let len = self.pending().len();
+        let next = (1..len).find(|i| self.starts_with_magic(*i)).unwrap_or(len);
+
+        self.consume(next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecodeError, Endian, IntEncoding};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::string::String;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(Checksum::Crc16.compute(b""), 0xffff);
+        assert_eq!(Checksum::Crc16.compute(b"123456789"), 0x29b1);
+        assert_eq!(Checksum::Crc32.compute(b""), 0);
+        assert_eq!(Checksum::Crc32.compute(b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            Checksum::Crc32.compute(b"The quick brown fox jumps over the lazy dog"),
+            0x414f_a339
+        );
+    }
+
+    #[test]
+    fn test_frame_format() {
+        let config = FrameConfig::new()
+            .with_magic(&[0xaa])
+            .with_checksum(Checksum::Crc16)
+            .with_config(Config::new().with_endian(Endian::Big));
+        let frame = FrameEncoder::new(config).encode(&0x0102u16).unwrap();
+        let checksum = Checksum::Crc16.compute(&[0, 0, 0, 2, 1, 2]) as u16;
+
+        assert_eq!(frame.len(), 2 + config.overhead());
+        assert_eq!(&frame[..7], &[0xaa, 0, 0, 0, 2, 1, 2]);
+        assert_eq!(&frame[7..], &checksum.to_be_bytes());
+    }
+
+    #[test]
+    fn test_byte_at_a_time() {
+        let config =
+            FrameConfig::new().with_config(Config::new().with_length_encoding(IntEncoding::VarInt));
+        let encoder = FrameEncoder::new(config);
+        let mut decoder = FrameDecoder::new(config);
+        let mut stream = Vec::new();
+
+        for message in ["first", "", "third"] {
+            encoder.encode_to_sink(message, &mut stream).unwrap();
+        }
+
+        let mut received = Vec::new();
+
+        for b in stream {
+            decoder.push(&[b]);
+
+            while let Some(message) = decoder.next_frame::<String>().unwrap() {
+                received.push(message);
+            }
+        }
+
+        assert_eq!(received, ["first", "", "third"]);
+        assert_eq!(decoder.buffered_len(), 0);
+        assert_eq!(decoder.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let encoder = FrameEncoder::new(FrameConfig::new());
+        let mut decoder = FrameDecoder::new(FrameConfig::new());
+        let frame = encoder.encode(&5u32).unwrap();
+
+        decoder.push(&frame[..3]);
+        assert_eq!(decoder.next_frame::<u32>(), Ok(None));
+        assert_eq!(
+            decoder.finish(),
+            Err(FrameError::Truncated {
+                needed: 6,
+                available: 3
+            })
+        );
+
+        decoder.push(&frame[3..frame.len() - 1]);
+        assert_eq!(decoder.next_frame::<u32>(), Ok(None));
+        assert_eq!(
+            decoder.finish(),
+            Err(FrameError::Truncated {
+                needed: 14,
+                available: 13
+            })
+        );
+
+        // A frame cut short by the start of the next frame fails its checksum, the next frame
+        // is still found
+        let mut decoder = FrameDecoder::new(FrameConfig::new());
+        decoder.push(&frame[..8]);
+        decoder.push(&encoder.encode(&6u32).unwrap());
+
+        assert!(matches!(
+            decoder.next_frame::<u32>(),
+            Err(FrameError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(decoder.next_frame(), Ok(Some(6u32)));
+        assert_eq!(decoder.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_oversized() {
+        let config = FrameConfig::new().with_max_payload_len(4);
+        let encoder = FrameEncoder::new(config);
+        let mut decoder = FrameDecoder::new(config);
+
+        assert_eq!(
+            encoder.encode(&5u64),
+            Err(EncodeError::FrameTooLarge { length: 8, max: 4 })
+        );
+
+        // Only the header of an oversized frame is needed to reject it
+        let frame = FrameEncoder::new(FrameConfig::new()).encode(&5u64).unwrap();
+        decoder.push(&frame[..6]);
+
+        assert_eq!(
+            decoder.next_frame::<u64>(),
+            Err(FrameError::FrameTooLarge { length: 8, max: 4 })
+        );
+        assert_eq!(decoder.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let encoder = FrameEncoder::new(FrameConfig::new());
+        let mut decoder = FrameDecoder::new(FrameConfig::new());
+
+        decoder.push(b"xyzB");
+        assert_eq!(decoder.next_frame::<u8>(), Err(FrameError::InvalidMagic));
+        assert_eq!(decoder.buffered_len(), 1);
+
+        decoder.push(&encoder.encode(&1u8).unwrap()[1..]);
+        assert_eq!(decoder.next_frame(), Ok(Some(1u8)));
+    }
+
+    #[test]
+    fn test_invalid_payload() {
+        let encoder = FrameEncoder::new(FrameConfig::new());
+        let mut decoder = FrameDecoder::new(FrameConfig::new());
+
+        decoder.push(&encoder.encode(&2u8).unwrap());
+        decoder.push(&encoder.encode(&1u16).unwrap());
+        decoder.push(&encoder.encode(&3u8).unwrap());
+
+        assert_eq!(
+            decoder.next_frame::<bool>(),
+            Err(FrameError::Decode(DecodeError::InvalidBool { value: 2 }))
+        );
+        assert_eq!(
+            decoder.next_frame::<u8>(),
+            Err(FrameError::TrailingBytes { count: 1 })
+        );
+        assert_eq!(decoder.next_payload(), Ok(Some(vec![3])));
+    }
+
+    #[test]
+    fn test_many_frames() {
+        let encoder = FrameEncoder::new(FrameConfig::new());
+        let mut decoder = FrameDecoder::new(FrameConfig::new());
+        let frame_len = encoder.encode(&0u32).unwrap().len();
+        let mut stream = Vec::new();
+
+        for i in 0..1000u32 {
+            encoder.encode_to_sink(&i, &mut stream).unwrap();
+        }
+
+        // The last frame is split so consumed bytes are compacted by the second push
+        decoder.push(&stream[..stream.len() - 1]);
+
+        for i in 0..999u32 {
+            assert_eq!(decoder.next_frame(), Ok(Some(i)));
+            assert_eq!(decoder.buffered_len(), (999 - i as usize) * frame_len - 1);
+        }
+
+        assert_eq!(decoder.next_frame::<u32>(), Ok(None));
+
+        decoder.push(&stream[stream.len() - 1..]);
+
+        assert_eq!(decoder.buffer.len(), frame_len);
+        assert_eq!(decoder.next_frame(), Ok(Some(999u32)));
+        assert_eq!(decoder.finish(), Ok(()));
+    }
+}
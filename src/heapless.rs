@@ -0,0 +1,176 @@
+#[cfg(feature = "bool_arr_optimization")]
+use core::any::{Any, TypeId};
+
+use ::heapless::{String, Vec};
+
+use crate::{BorrowDecodable, Decodable, DecodeError, Decoder, Encodable, EncodeError, Encoder};
+
+/// Encoded in the same format as a `Vec`, so the two are interchangeable.
+#[cfg(feature = "bool_arr_optimization")]
+impl<T: Encodable + Any, const N: usize> Encodable for Vec<T, N> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_slice().encode_to(encoder);
+    }
+}
+
+/// Encoded in the same format as a `Vec`, so the two are interchangeable.
+#[cfg(not(feature = "bool_arr_optimization"))]
+impl<T: Encodable, const N: usize> Encodable for Vec<T, N> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_slice().encode_to(encoder);
+    }
+}
+
+/// Encoded in the same format as a `String`, so the two are interchangeable.
+impl<const N: usize> Encodable for String<N> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_str().encode_to(encoder);
+    }
+}
+
+/// Reads a length prefix, rejecting lengths larger than the capacity `N`.
+fn read_bounded_length<const N: usize>(decoder: &mut Decoder<'_>) -> Result<usize, DecodeError> {
+    let length = decoder.read_length_prefix()?;
+
+    if length > N {
+        return Err(DecodeError::CollectionTooLong { length, max: N });
+    }
+
+    return Ok(length);
+}
+
+/// Decodes `len` elements, which must not exceed the capacity `N`.
+fn decode_elements<T: Decodable, const N: usize>(
+    decoder: &mut Decoder<'_>,
+    len: usize,
+) -> Result<Vec<T, N>, DecodeError> {
+    let mut vec = Vec::new();
+
+    for _ in 0..len {
+        let Ok(()) = vec.push(T::decode_from(decoder)?) else {
+            unreachable!("the length was checked against the capacity");
+        };
+    }
+
+    return Ok(vec);
+}
+
+/// A length larger than the capacity is rejected with [DecodeError::CollectionTooLong].
+#[cfg(feature = "bool_arr_optimization")]
+impl<T: Decodable + Any, const N: usize> Decodable for Vec<T, N> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = read_bounded_length::<N>(decoder)?;
+
+        if TypeId::of::<T>() == TypeId::of::<bool>() {
+            // An array of N bytes always has room for the len / 8 bytes of packed values
+            let mut bytes = [0u8; N];
+            decoder.read_into(&mut bytes[..len.div_ceil(8)])?;
+
+            return Ok((0..len)
+                .map(|i| {
+                    let b = bytes[i / 8] & (1 << (i % 8)) != 0;
+
+                    return unsafe { core::mem::transmute_copy::<bool, T>(&b) };
+                })
+                .collect());
+        } else {
+            return decoder.nested(|decoder| decode_elements(decoder, len));
+        }
+    }
+}
+
+/// A length larger than the capacity is rejected with [DecodeError::CollectionTooLong].
+#[cfg(not(feature = "bool_arr_optimization"))]
+impl<T: Decodable, const N: usize> Decodable for Vec<T, N> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = read_bounded_length::<N>(decoder)?;
+
+        return decoder.nested(|decoder| decode_elements(decoder, len));
+    }
+}
+
+/// A length larger than the capacity is rejected with [DecodeError::CollectionTooLong].
+impl<const N: usize> Decodable for String<N> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = read_bounded_length::<N>(decoder)?;
+        let mut bytes = Vec::<u8, N>::new();
+
+        let Ok(()) = bytes.resize_default(len) else {
+            unreachable!("the length was checked against the capacity");
+        };
+
+        decoder.read_into(&mut bytes)?;
+
+        return String::from_utf8(bytes).map_err(|e| DecodeError::InvalidUtf8 {
+            valid_up_to: e.valid_up_to(),
+        });
+    }
+}
+
+impl<'de, T, const N: usize> BorrowDecodable<'de> for Vec<T, N>
+where
+    Vec<T, N>: Decodable,
+{
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return Self::decode_from(decoder);
+    }
+}
+
+impl<'de, const N: usize> BorrowDecodable<'de> for String<N> {
+    fn borrow_decode_from(decoder: &mut Decoder<'de>) -> Result<Self, DecodeError> {
+        return Self::decode_from(decoder);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heapless_vec() {
+        let mut buf = [0u8; 32];
+        let value: Vec<u16, 4> = Vec::from_slice(&[1, 2, 3]).unwrap();
+        let len = value.encode_to_slice(&mut buf).unwrap();
+
+        assert_eq!(&buf[..len], &[3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0, 3, 0]);
+        assert_eq!(Vec::<u16, 4>::decode(&buf[..len]), Ok(value));
+        assert_eq!(
+            Vec::<u16, 2>::decode(&buf[..len]),
+            Err(DecodeError::CollectionTooLong { length: 3, max: 2 })
+        );
+    }
+
+    #[test]
+    fn test_heapless_bool_vec() {
+        let mut buf = [0u8; 32];
+        let value: Vec<bool, 12> = Vec::from_slice(&[true; 10]).unwrap();
+        let len = value.encode_to_slice(&mut buf).unwrap();
+
+        // The bools are packed into 2 bytes
+        #[cfg(feature = "bool_arr_optimization")]
+        assert_eq!(len, 10);
+        #[cfg(not(feature = "bool_arr_optimization"))]
+        assert_eq!(len, 18);
+
+        assert_eq!(Vec::<bool, 12>::decode(&buf[..len]), Ok(value));
+        assert!(Vec::<bool, 12>::decode(&buf[..len - 1]).is_err());
+    }
+
+    #[test]
+    fn test_heapless_string() {
+        let mut buf = [0u8; 16];
+        let value: String<4> = String::try_from("abc").unwrap();
+        let len = value.encode_to_slice(&mut buf).unwrap();
+
+        assert_eq!(&buf[..len], &[3, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', b'c']);
+        assert_eq!(String::<4>::decode(&buf[..len]), Ok(value));
+        assert_eq!(
+            String::<2>::decode(&buf[..len]),
+            Err(DecodeError::CollectionTooLong { length: 3, max: 2 })
+        );
+        assert_eq!(
+            String::<4>::decode(&[1, 0, 0, 0, 0, 0, 0, 0, 0xff]),
+            Err(DecodeError::InvalidUtf8 { valid_up_to: 0 })
+        );
+    }
+}
@@ -9,11 +9,24 @@
 //!
 //! ### Features
 //! - `derive` - Enables the derive macros for enums and structs (default)
-//! - `std` - Enables features which required std (default)
-//! - `coder` - Enables the [Coder] struct as a simple front end for decoding multiple objects
+//! - `std` - Enables features which required std, implies `alloc` (default)
+//! - `alloc` - Enables the implementations for heap allocated types such as `Vec` and `String`,
+//!   and the methods which return a `Vec` (default)
+//! - `coder` - Enables the [Coder] struct as a simple front end for decoding multiple objects,
+//!   implies `alloc`
+//! - `framing` - Enables the [FrameEncoder] and [FrameDecoder] for sending values over a stream
+//!   in frames with a magic number, length and checksum, implies `alloc` (default)
+//! - `heapless` - Implements the traits for the bounded `Vec` and `String` types of the
+//!   `heapless` crate
+//!
+//! Without the `alloc` feature the crate does not allocate. Values are encoded into a
+//! `&mut [u8]` using `encode_to_slice`, and integers, arrays, tuples, `&str`, `&[u8]` and derived
+//! types can be encoded and decoded. The `post_enc_func` hook is not available without `alloc`.
 //!
 //! # Example
 //! ```
+//! # #[cfg(all(feature = "derive", feature = "alloc"))]
+//! # {
 //! use byte_coding::*;
 //!
 //! #[derive(Encodable, Decodable, PartialEq, Debug)]
@@ -25,39 +38,71 @@
 //!     field_4: u8
 //! }
 //!
-//! fn main() {
-//!     let example = Example {
-//!         field_1: u64::MAX,
-//!         field_2: 0,
-//!         field_3: u16::MAX,
-//!         field_4: 0    
-//!     };
+//! let example = Example {
+//!     field_1: u64::MAX,
+//!     field_2: 0,
+//!     field_3: u16::MAX,
+//!     field_4: 0
+//! };
 //!
-//!     // Encode example sturct into a vector of bytes
-//!     let encoded_bytes = example.encoded();
-//!     assert_eq!(encoded_bytes, vec![0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0]);
+//! // Encode example sturct into a vector of bytes
+//! let encoded_bytes = example.encoded();
+//! assert_eq!(encoded_bytes, vec![0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0]);
 //!
-//!     let decoded_example: Example = Decodable::decode(&encoded_bytes).unwrap();
-//!     assert_eq!(decoded_example, example);
-//! }
+//! let decoded_example: Example = Decodable::decode(&encoded_bytes).unwrap();
+//! assert_eq!(decoded_example, example);
+//! # }
 //! ```
 //!
-//! See the [Encodable] and [Decodable] traits for further details
+//! See the [Encodable] and [Decodable] traits for further details. Values such as `&str` and
+//! `&[u8]` can be decoded without copying using the [BorrowDecodable] trait. The resources used
+//! while decoding are limited by [DecodeLimits], the defaults are safe for untrusted input. The
+//! byte order, length prefix width and enum tag width can be changed using a [Config]. The
+//! encoded size of a value is returned by [Encodable::encoded_len], and is available as a
+//! constant for types which implement [FixedSize]. Values can be encoded into any [ByteSink],
+//! such as a `&mut [u8]` or a `std::io::Write` using a [WriteSink], and decoded from a
+//! `std::io::Read` using [Decodable::decode_from_reader].
 
 #![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::needless_return)]
 
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 extern crate alloc;
 
+mod borrow_decodable;
+mod config;
 mod decodable;
+mod decoder;
 mod encodable;
+mod encoder;
+mod error;
+mod fixed_size;
+#[cfg(feature = "heapless")]
+mod heapless;
+mod sink;
+mod unprefixed;
+mod varint;
 
 #[cfg(feature = "coder")]
 mod coder;
 #[cfg(feature = "coder")]
 pub use coder::Coder;
 
+#[cfg(feature = "framing")]
+mod framing;
+#[cfg(feature = "framing")]
+pub use framing::*;
+
+pub use borrow_decodable::*;
 #[cfg(feature = "derive")]
 pub use byte_coding_derive::*;
+pub use config::*;
 pub use decodable::*;
+pub use decoder::*;
 pub use encodable::*;
+pub use encoder::*;
+pub use error::*;
+pub use fixed_size::*;
+pub use sink::*;
+pub use unprefixed::*;
+pub use varint::*;
@@ -0,0 +1,262 @@
+use core::hash::Hasher;
+
+use crate::EncodeError;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+
+/// A destination for encoded bytes.
+///
+/// An [Encoder](crate::Encoder) writes to any sink, implementations are provided for `Vec<u8>`,
+/// for `&mut [u8]` which is advanced past the written bytes in the same way as
+/// `std::io::Write`, for any `std::io::Write` using [WriteSink], and for [CountingSink] and
+/// [HashSink] which do not store the bytes.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{ByteSink, Encodable};
+///
+/// let mut storage = [0u8; 4];
+/// let mut sink = &mut storage[..];
+///
+/// 1u16.encode_to_sink(&mut sink).unwrap();
+/// assert_eq!(sink.len(), 2);
+/// assert!(5u32.encode_to_sink(&mut sink).is_err());
+/// assert_eq!(storage, [1, 0, 0, 0]);
+/// ```
+pub trait ByteSink {
+    /// Writes all of the bytes to the sink. If the bytes cannot be written an error is returned,
+    /// in which case some of the bytes may have been written.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError>;
+}
+
+#[cfg(feature = "alloc")]
+impl ByteSink for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.extend_from_slice(bytes);
+
+        return Ok(());
+    }
+}
+
+/// The slice is advanced past the written bytes. If there is not enough space nothing is
+/// written and [EncodeError::BufferFull] is returned.
+impl ByteSink for &mut [u8] {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        if self.len() < bytes.len() {
+            return Err(EncodeError::BufferFull {
+                needed: bytes.len(),
+                available: self.len(),
+            });
+        }
+
+        let (dest, rest) = core::mem::take(self).split_at_mut(bytes.len());
+        dest.copy_from_slice(bytes);
+        *self = rest;
+
+        return Ok(());
+    }
+}
+
+impl<S: ByteSink + ?Sized> ByteSink for &mut S {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        return (**self).write_bytes(bytes);
+    }
+}
+
+/// Writes encoded bytes to a `std::io::Write`. Each write uses `write_all`, so a buffered writer
+/// should be used when writing to a file or socket.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{Encodable, WriteSink};
+/// use std::io::Cursor;
+///
+/// let mut sink = WriteSink::new(Cursor::new(Vec::new()));
+///
+/// "ab".encode_to_sink(&mut sink).unwrap();
+/// assert_eq!(sink.into_inner().into_inner(), vec![2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct WriteSink<W> {
+    writer: W,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> WriteSink<W> {
+    /// Creates a sink which writes to the writer.
+    pub fn new(writer: W) -> Self {
+        return Self { writer };
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        return &self.writer;
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        return &mut self.writer;
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        return self.writer;
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> ByteSink for WriteSink<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        return self
+            .writer
+            .write_all(bytes)
+            .map_err(|e| EncodeError::Io(e.kind()));
+    }
+}
+
+/// Counts the bytes written without storing them. This can be used to find the encoded size of
+/// a value without allocating.
+///
+/// ## Example
+/// ```
+/// use byte_coding::{CountingSink, Encodable};
+///
+/// let mut sink = CountingSink::new();
+///
+/// (1u32, "ab").encode_to_sink(&mut sink).unwrap();
+/// assert_eq!(sink.count(), 14);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CountingSink {
+    count: usize,
+}
+
+impl CountingSink {
+    /// Creates a sink which has counted zero bytes.
+    pub const fn new() -> Self {
+        return Self { count: 0 };
+    }
+
+    /// Returns the number of bytes written so far.
+    pub const fn count(&self) -> usize {
+        return self.count;
+    }
+}
+
+impl ByteSink for CountingSink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.count += bytes.len();
+
+        return Ok(());
+    }
+}
+
+/// Feeds the bytes written into a `Hasher` without storing them. This can be used to hash the
+/// encoded form of a value without allocating.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Encodable, HashSink};
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::Hasher;
+///
+/// let mut sink = HashSink::new(DefaultHasher::new());
+/// "test".encode_to_sink(&mut sink).unwrap();
+///
+/// let mut hasher = DefaultHasher::new();
+/// hasher.write(&"test".encoded());
+///
+/// assert_eq!(sink.finish(), hasher.finish());
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HashSink<H> {
+    hasher: H,
+}
+
+impl<H: Hasher> HashSink<H> {
+    /// Creates a sink which writes to the hasher.
+    pub fn new(hasher: H) -> Self {
+        return Self { hasher };
+    }
+
+    /// Returns the hash of the bytes written so far.
+    pub fn finish(&self) -> u64 {
+        return self.hasher.finish();
+    }
+
+    /// Returns the underlying hasher.
+    pub fn into_inner(self) -> H {
+        return self.hasher;
+    }
+}
+
+impl<H: Hasher> ByteSink for HashSink<H> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.hasher.write(bytes);
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(feature = "alloc", not(feature = "std")))]
+    use alloc::vec;
+
+    #[test]
+    fn test_slice_sink() {
+        let mut storage = [0u8; 3];
+        let mut sink = &mut storage[..];
+
+        assert!(sink.write_bytes(&[1, 2]).is_ok());
+        assert_eq!(
+            sink.write_bytes(&[3, 4]),
+            Err(EncodeError::BufferFull {
+                needed: 2,
+                available: 1
+            })
+        );
+        assert!(sink.write_bytes(&[3]).is_ok());
+        assert!(sink.is_empty());
+        assert_eq!(storage, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_counting_sink() {
+        let mut sink = CountingSink::new();
+
+        sink.write_bytes(&[1, 2, 3]).unwrap();
+        sink.write_bytes(&[]).unwrap();
+
+        assert_eq!(sink.count(), 3);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_vec_sink() {
+        let mut sink = vec![1u8];
+
+        sink.write_bytes(&[2, 3]).unwrap();
+
+        assert_eq!(sink, vec![1, 2, 3]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_sink_error() {
+        let mut storage = [0u8; 1];
+        let mut sink = WriteSink::new(std::io::Cursor::new(&mut storage[..]));
+
+        assert_eq!(
+            sink.write_bytes(&[1, 2]),
+            Err(EncodeError::Io(std::io::ErrorKind::WriteZero))
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use crate::{CountingSink, DecodeError, Decoder, Encodable, EncodeError, Encoder};
+
+#[cfg(feature = "alloc")]
+use crate::decodable::decode_elements;
+#[cfg(feature = "alloc")]
+use crate::Decodable;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::String;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::marker::PhantomData;
+
+/// Provide methods to encode a collection or string without its length prefix.
+///
+/// This is used by derived implementations for fields with the `count` or `byte_len` attributes,
+/// where the length is stored in another field instead. `count` is the value the length prefix
+/// would have had, which is the number of elements of a collection and the number of bytes of a
+/// string. The elements are written one after another, so an array of `bool` is not packed when
+/// the `bool_arr_optimization` feature is enabled.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Encoder, UnprefixedEncodable};
+///
+/// let items = vec![1u16, 2];
+/// let mut buf = Vec::new();
+/// let mut encoder = Encoder::new(&mut buf);
+///
+/// assert_eq!(items.count(), 2);
+/// assert_eq!(items.byte_len(&encoder), Ok(4));
+///
+/// items.encode_unprefixed(&mut encoder).unwrap();
+/// assert_eq!(buf, vec![1, 0, 2, 0]);
+/// # }
+/// ```
+pub trait UnprefixedEncodable {
+    /// Returns the number of elements, or bytes for a string.
+    fn count(&self) -> usize;
+
+    /// Writes the elements without the length prefix.
+    fn encode_unprefixed(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError>;
+
+    /// Returns the number of bytes `encode_unprefixed` writes using the configuration of the
+    /// encoder. The provided implementation encodes the value into a [CountingSink], use
+    /// [UnprefixedBytes] to write the value after its length without encoding it twice.
+    fn byte_len(&self, encoder: &Encoder<'_>) -> Result<usize, EncodeError> {
+        let mut counter = CountingSink::new();
+
+        self.encode_unprefixed(&mut Encoder::with_config(&mut counter, *encoder.config()))?;
+
+        return Ok(counter.count());
+    }
+}
+
+/// A value which has been prepared to be written by
+/// [encode_unprefixed](UnprefixedEncodable::encode_unprefixed), once its length in bytes is
+/// known.
+///
+/// This is used by derived implementations for fields with the `byte_len` attribute. With the
+/// `alloc` feature the value is encoded once into a buffer, which `write_to` copies to the
+/// encoder. Without it only the length is kept and `write_to` encodes the value again, so a value
+/// which contains `n` nested levels of `byte_len` fields is encoded `2^n` times.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Encoder, UnprefixedBytes};
+///
+/// let items = vec![1u16, 2];
+/// let mut buf = Vec::new();
+/// let mut encoder = Encoder::new(&mut buf);
+/// let bytes = UnprefixedBytes::new(&items, &encoder).unwrap();
+///
+/// assert_eq!(bytes.len(), 4);
+///
+/// bytes.write_to(&mut encoder).unwrap();
+/// assert_eq!(buf, vec![1, 0, 2, 0]);
+/// # }
+/// ```
+pub struct UnprefixedBytes<'a, T: UnprefixedEncodable + ?Sized> {
+    #[cfg(feature = "alloc")]
+    bytes: Vec<u8>,
+    #[cfg(feature = "alloc")]
+    value: PhantomData<&'a T>,
+    #[cfg(not(feature = "alloc"))]
+    value: &'a T,
+    #[cfg(not(feature = "alloc"))]
+    len: usize,
+}
+
+impl<'a, T: UnprefixedEncodable + ?Sized> UnprefixedBytes<'a, T> {
+    /// Prepares the value to be written using the configuration of the encoder.
+    #[cfg(feature = "alloc")]
+    pub fn new(value: &'a T, encoder: &Encoder<'_>) -> Result<Self, EncodeError> {
+        let mut bytes = Vec::new();
+
+        value.encode_unprefixed(&mut Encoder::with_config(&mut bytes, *encoder.config()))?;
+
+        return Ok(Self {
+            bytes,
+            value: PhantomData,
+        });
+    }
+
+    /// Prepares the value to be written using the configuration of the encoder.
+    #[cfg(not(feature = "alloc"))]
+    pub fn new(value: &'a T, encoder: &Encoder<'_>) -> Result<Self, EncodeError> {
+        let len = value.byte_len(encoder)?;
+
+        return Ok(Self { value, len });
+    }
+
+    /// Returns the number of bytes `write_to` writes.
+    #[cfg(feature = "alloc")]
+    pub fn len(&self) -> usize {
+        return self.bytes.len();
+    }
+
+    /// Returns the number of bytes `write_to` writes.
+    #[cfg(not(feature = "alloc"))]
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    /// Whether the value is encoded as no bytes.
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    /// Writes the value without its length prefix.
+    #[cfg(feature = "alloc")]
+    pub fn write_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return encoder.write_bytes(&self.bytes);
+    }
+
+    /// Writes the value without its length prefix.
+    #[cfg(not(feature = "alloc"))]
+    pub fn write_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.value.encode_unprefixed(encoder);
+    }
+}
+
+/// Provide methods to decode a collection or string which was encoded without its length prefix.
+///
+/// This is the counterpart of [UnprefixedEncodable], the length is read from another field by
+/// derived implementations. The lifetime `'de` is the lifetime of the input buffer, which allows
+/// `&str` and `&[u8]` to be borrowed from it.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Decoder, UnprefixedDecodable};
+///
+/// let mut decoder = Decoder::new(&[1, 0, 2, 0, 3, 0]);
+///
+/// assert_eq!(Vec::<u16>::decode_count(&mut decoder, 2), Ok(vec![1, 2]));
+/// assert_eq!(Vec::<u16>::decode_byte_len(&mut decoder, 2), Ok(vec![3]));
+/// # }
+/// ```
+pub trait UnprefixedDecodable<'de>: Sized {
+    /// Reads `count` elements, or bytes for a string.
+    fn decode_count(decoder: &mut Decoder<'de>, count: usize) -> Result<Self, DecodeError>;
+
+    /// Reads elements until exactly `len` bytes have been read.
+    fn decode_byte_len(decoder: &mut Decoder<'de>, len: usize) -> Result<Self, DecodeError>;
+}
+
+impl<T: UnprefixedEncodable + ?Sized> UnprefixedEncodable for &T {
+    fn count(&self) -> usize {
+        return T::count(self);
+    }
+
+    fn encode_unprefixed(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return T::encode_unprefixed(self, encoder);
+    }
+
+    fn byte_len(&self, encoder: &Encoder<'_>) -> Result<usize, EncodeError> {
+        return T::byte_len(self, encoder);
+    }
+}
+
+impl<T: Encodable> UnprefixedEncodable for [T] {
+    fn count(&self) -> usize {
+        return self.len();
+    }
+
+    fn encode_unprefixed(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        for item in self.iter() {
+            item.encode_to(encoder)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl UnprefixedEncodable for str {
+    fn count(&self) -> usize {
+        return self.len();
+    }
+
+    fn encode_unprefixed(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return encoder.write_bytes(self.as_bytes());
+    }
+
+    fn byte_len(&self, _encoder: &Encoder<'_>) -> Result<usize, EncodeError> {
+        return Ok(self.len());
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Encodable> UnprefixedEncodable for Vec<T> {
+    fn count(&self) -> usize {
+        return self.len();
+    }
+
+    fn encode_unprefixed(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_slice().encode_unprefixed(encoder);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl UnprefixedEncodable for String {
+    fn count(&self) -> usize {
+        return self.len();
+    }
+
+    fn encode_unprefixed(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return self.as_str().encode_unprefixed(encoder);
+    }
+
+    fn byte_len(&self, _encoder: &Encoder<'_>) -> Result<usize, EncodeError> {
+        return Ok(self.len());
+    }
+}
+
+impl<'a, 'de: 'a> UnprefixedDecodable<'de> for &'a [u8] {
+    fn decode_count(decoder: &mut Decoder<'de>, count: usize) -> Result<Self, DecodeError> {
+        return decoder.read_bytes(count);
+    }
+
+    fn decode_byte_len(decoder: &mut Decoder<'de>, len: usize) -> Result<Self, DecodeError> {
+        return decoder.read_bytes(len);
+    }
+}
+
+impl<'a, 'de: 'a> UnprefixedDecodable<'de> for &'a str {
+    fn decode_count(decoder: &mut Decoder<'de>, count: usize) -> Result<Self, DecodeError> {
+        return core::str::from_utf8(decoder.read_bytes(count)?).map_err(|e| {
+            DecodeError::InvalidUtf8 {
+                valid_up_to: e.valid_up_to(),
+            }
+        });
+    }
+
+    fn decode_byte_len(decoder: &mut Decoder<'de>, len: usize) -> Result<Self, DecodeError> {
+        return Self::decode_count(decoder, len);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Decodable> UnprefixedDecodable<'de> for Vec<T> {
+    fn decode_count(decoder: &mut Decoder<'de>, count: usize) -> Result<Self, DecodeError> {
+        decoder.claim_length::<T>(count)?;
+
+        return decoder.nested(|decoder| decode_elements(decoder, count));
+    }
+
+    fn decode_byte_len(decoder: &mut Decoder<'de>, len: usize) -> Result<Self, DecodeError> {
+        // Every element which is not empty uses at least one byte, so there are at most `len`
+        decoder.claim_length::<T>(len)?;
+
+        return decoder.limited(len, |decoder| {
+            return decoder.nested(|decoder| {
+                let mut vec = Vec::with_capacity(decoder.capacity_hint(len));
+
+                while !decoder.is_exhausted() {
+                    // Elements with an empty encoding would never reach the end of the bytes
+                    if vec.len() == len {
+                        return Err(DecodeError::ByteLenMismatch);
+                    }
+
+                    vec.push(T::decode_from(decoder)?);
+                }
+
+                return Ok(vec);
+            });
+        });
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> UnprefixedDecodable<'de> for String {
+    fn decode_count(decoder: &mut Decoder<'de>, count: usize) -> Result<Self, DecodeError> {
+        decoder.claim_length::<u8>(count)?;
+
+        return String::from_utf8(decoder.read_byte_vec(count)?).map_err(|e| {
+            DecodeError::InvalidUtf8 {
+                valid_up_to: e.utf8_error().valid_up_to(),
+            }
+        });
+    }
+
+    fn decode_byte_len(decoder: &mut Decoder<'de>, len: usize) -> Result<Self, DecodeError> {
+        return Self::decode_count(decoder, len);
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    fn encode_unprefixed<T: UnprefixedEncodable + ?Sized>(value: &T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut encoder = Encoder::new(&mut buf);
+        let len = value.byte_len(&encoder).unwrap();
+
+        value.encode_unprefixed(&mut encoder).unwrap();
+        assert_eq!(buf.len(), len);
+
+        return buf;
+    }
+
+    #[test]
+    fn test_encode_unprefixed() {
+        assert_eq!(encode_unprefixed(&vec![1u16, 2]), vec![1, 0, 2, 0]);
+        assert_eq!(encode_unprefixed("ab"), vec![b'a', b'b']);
+        assert_eq!(encode_unprefixed(&&[true, false][..]), vec![1, 0]);
+
+        assert_eq!(vec![1u16, 2].count(), 2);
+        assert_eq!(String::from("ab").count(), 2);
+        assert_eq!(
+            vec![String::from("ab")].byte_len(&Encoder::new(&mut CountingSink::new())),
+            Ok(10)
+        );
+    }
+
+    #[test]
+    fn test_decode_count() {
+        let mut decoder = Decoder::new(&[1, 0, 2, 0, b'a', b'b', b'c']);
+
+        assert_eq!(Vec::<u16>::decode_count(&mut decoder, 2), Ok(vec![1, 2]));
+        assert_eq!(<&str>::decode_count(&mut decoder, 2), Ok("ab"));
+        assert_eq!(String::decode_count(&mut decoder, 1), Ok(String::from("c")));
+        assert_eq!(
+            <&[u8]>::decode_count(&mut decoder, 1),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 1,
+                available: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_byte_len() {
+        let mut decoder = Decoder::new(&[1, 0, 2, 0, 3]);
+
+        assert_eq!(Vec::<u16>::decode_byte_len(&mut decoder, 4), Ok(vec![1, 2]));
+        assert_eq!(<&[u8]>::decode_byte_len(&mut decoder, 1), Ok(&[3][..]));
+
+        // The last element would need more bytes than are left
+        assert_eq!(
+            Vec::<u16>::decode_byte_len(&mut Decoder::new(&[1, 0, 2]), 3),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 2,
+                available: 1
+            })
+        );
+        assert_eq!(
+            Vec::<()>::decode_byte_len(&mut Decoder::new(&[0]), 1),
+            Err(DecodeError::ByteLenMismatch)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_byte_len_reader() {
+        let mut reader: &[u8] = &[1, 0, 2, 0, 3];
+        let mut decoder = Decoder::from_reader(&mut reader);
+
+        assert_eq!(Vec::<u16>::decode_byte_len(&mut decoder, 4), Ok(vec![1, 2]));
+        assert_eq!(u8::decode_from(&mut decoder), Ok(3));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{Decodable, DecodeError, Decoder, Encodable, EncodeError, Encoder};
+
+/// An integer encoded as an unsigned LEB128 variable length integer.
+///
+/// Each byte stores 7 bits of the value, least significant first, so small values use fewer
+/// bytes than their fixed width encoding. A `u64` uses between 1 and 10 bytes. Signed integers
+/// are encoded as the unsigned integer with the same bits, so negative values always use the
+/// maximum length, [ZigZag] should be used for signed values which may be negative.
+///
+/// Decoding rejects encodings which are longer than necessary or which do not fit in `T`. The
+/// configured byte order does not affect variable length integers.
+///
+/// Derived implementations can encode a field using this format with the `varint` attribute,
+/// without changing the type of the field.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Decodable, Encodable, VarInt};
+///
+/// assert_eq!(VarInt(1u64).encoded(), vec![1]);
+/// assert_eq!(VarInt(300u64).encoded(), vec![0xac, 2]);
+/// assert_eq!(VarInt::<u16>::decode(&[0xac, 2]), Ok(VarInt(300)));
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VarInt<T>(pub T);
+
+/// A signed integer encoded using zigzag encoding followed by an unsigned LEB128 variable length
+/// integer.
+///
+/// Zigzag encoding maps values with a small magnitude to small unsigned values, `0, -1, 1, -2`
+/// become `0, 1, 2, 3`, so both small positive and small negative values use few bytes.
+///
+/// Decoding rejects encodings which are longer than necessary or which do not fit in `T`.
+/// Derived implementations can encode a field using this format with the `zigzag` attribute.
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use byte_coding::{Decodable, Encodable, ZigZag};
+///
+/// assert_eq!(ZigZag(-1i32).encoded(), vec![1]);
+/// assert_eq!(ZigZag(1i32).encoded(), vec![2]);
+/// assert_eq!(ZigZag::<i64>::decode(&[3]), Ok(ZigZag(-2)));
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZigZag<T>(pub T);
+
+macro_rules! impl_varint_unsigned {
+    ($($ty:ident),+) => {
+        $(
+            impl Encodable for VarInt<$ty> {
+                fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+                    return encoder.write_varint(self.0 as u128);
+                }
+            }
+
+            impl Decodable for VarInt<$ty> {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    return Ok(Self(decoder.read_varint($ty::BITS)? as $ty));
+                }
+            }
+        )+
+    };
+}
+
+impl_varint_unsigned!(u8, u16, u32, u64, u128);
+
+macro_rules! impl_varint_signed {
+    ($($ty:ident $unsigned:ident),+) => {
+        $(
+            impl Encodable for VarInt<$ty> {
+                fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+                    return encoder.write_varint(self.0 as $unsigned as u128);
+                }
+            }
+
+            impl Decodable for VarInt<$ty> {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    return Ok(Self(decoder.read_varint($unsigned::BITS)? as $unsigned as $ty));
+                }
+            }
+
+            impl Encodable for ZigZag<$ty> {
+                fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+                    let v = (self.0 << 1) ^ (self.0 >> ($ty::BITS - 1));
+
+                    return encoder.write_varint(v as $unsigned as u128);
+                }
+            }
+
+            impl Decodable for ZigZag<$ty> {
+                fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    let v = decoder.read_varint($unsigned::BITS)? as $unsigned;
+
+                    return Ok(Self((v >> 1) as $ty ^ -((v & 1) as $ty)));
+                }
+            }
+        )+
+    };
+}
+
+impl_varint_signed!(i8 u8, i16 u16, i32 u32, i64 u64, i128 u128);
+
+/// Encoded in the same way as a `u64`, values which do not fit in a `usize` are rejected.
+impl Encodable for VarInt<usize> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return VarInt(self.0 as u64).encode_to(encoder);
+    }
+}
+
+/// Encoded in the same way as a `u64`, values which do not fit in a `usize` are rejected.
+impl Decodable for VarInt<usize> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let v = VarInt::<u64>::decode_from(decoder)?.0;
+
+        return usize::try_from(v)
+            .map(Self)
+            .map_err(|_| DecodeError::UnsignedIntegerOverflow { value: v });
+    }
+}
+
+/// Encoded in the same way as an `i64`, values which do not fit in an `isize` are rejected.
+impl Encodable for VarInt<isize> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return VarInt(self.0 as i64).encode_to(encoder);
+    }
+}
+
+/// Encoded in the same way as an `i64`, values which do not fit in an `isize` are rejected.
+impl Decodable for VarInt<isize> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let v = VarInt::<i64>::decode_from(decoder)?.0;
+
+        return isize::try_from(v)
+            .map(Self)
+            .map_err(|_| DecodeError::IntegerOverflow { value: v });
+    }
+}
+
+/// Encoded in the same way as an `i64`, values which do not fit in an `isize` are rejected.
+impl Encodable for ZigZag<isize> {
+    fn encode_to(&self, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        return ZigZag(self.0 as i64).encode_to(encoder);
+    }
+}
+
+/// Encoded in the same way as an `i64`, values which do not fit in an `isize` are rejected.
+impl Decodable for ZigZag<isize> {
+    fn decode_from(decoder: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let v = ZigZag::<i64>::decode_from(decoder)?.0;
+
+        return isize::try_from(v)
+            .map(Self)
+            .map_err(|_| DecodeError::IntegerOverflow { value: v });
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_varint_round_trip() {
+        for v in [0u64, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            assert_eq!(VarInt::decode(&VarInt(v).encoded()), Ok(VarInt(v)));
+        }
+
+        for v in [0i16, 1, -1, i16::MIN, i16::MAX] {
+            assert_eq!(VarInt::decode(&VarInt(v).encoded()), Ok(VarInt(v)));
+        }
+
+        assert_eq!(
+            VarInt::decode(&VarInt(u128::MAX).encoded()),
+            Ok(VarInt(u128::MAX))
+        );
+        assert_eq!(VarInt(-1i32).encoded(), vec![0xff, 0xff, 0xff, 0xff, 0x0f]);
+        assert_eq!(VarInt(usize::MAX).encoded().len(), 10);
+    }
+
+    #[test]
+    fn test_zigzag_round_trip() {
+        assert_eq!(ZigZag(0i64).encoded(), vec![0]);
+        assert_eq!(ZigZag(-64i64).encoded(), vec![127]);
+        assert_eq!(ZigZag(64i64).encoded(), vec![0x80, 1]);
+
+        for v in [0i8, 1, -1, i8::MIN, i8::MAX] {
+            assert_eq!(ZigZag::decode(&ZigZag(v).encoded()), Ok(ZigZag(v)));
+        }
+
+        for v in [0isize, -5, isize::MIN, isize::MAX] {
+            assert_eq!(ZigZag::decode(&ZigZag(v).encoded()), Ok(ZigZag(v)));
+        }
+
+        assert_eq!(
+            ZigZag::decode(&ZigZag(i128::MIN).encoded()),
+            Ok(ZigZag(i128::MIN))
+        );
+    }
+
+    #[test]
+    fn test_varint_invalid() {
+        // 256 does not fit in a u8
+        assert_eq!(
+            VarInt::<u8>::decode(&[0x80, 2]),
+            Err(DecodeError::InvalidVarInt)
+        );
+        // Overlong encoding of 1
+        assert_eq!(
+            VarInt::<u32>::decode(&[0x81, 0]),
+            Err(DecodeError::InvalidVarInt)
+        );
+        // Continuation bit on the last possible byte
+        assert_eq!(
+            ZigZag::<i16>::decode(&[0xff, 0xff, 0x83]),
+            Err(DecodeError::InvalidVarInt)
+        );
+        assert_eq!(
+            VarInt::<u64>::decode(&[0x80]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 1,
+                available: 0
+            })
+        );
+
+        let encoded: Vec<u8> = VarInt(u16::MAX as u32 + 1).encoded();
+        assert_eq!(
+            VarInt::<u16>::decode(&encoded),
+            Err(DecodeError::InvalidVarInt)
+        );
+    }
+}
@@ -1,6 +1,14 @@
-#[cfg(feature = "derive")]
+#![allow(clippy::needless_return)]
+
+#[cfg(all(feature = "derive", feature = "alloc"))]
 mod derive_tests {
     use byte_coding::*;
+    use std::borrow::Cow;
+    use std::cmp::Reverse;
+    use std::marker::PhantomData;
+    use std::num::{NonZeroU32, Wrapping};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     #[derive(Encodable, Decodable, Debug, PartialEq)]
     struct Example1(String);
@@ -83,6 +91,359 @@ mod derive_tests {
         V5,
     }
 
+    #[derive(Encodable, Decodable, Debug, PartialEq, Clone)]
+    struct Example8<T> {
+        id: u32,
+        body: T,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq, Clone)]
+    struct Example9<'a, T: Clone, const N: usize>
+    where
+        T: PartialEq,
+    {
+        data: [u8; N],
+        value: Option<T>,
+        #[byte_coding(ignore)]
+        label: Option<&'a str>,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq, Clone)]
+    #[byte_coding(
+        enc_bound = "T: Encodable + 'static",
+        dec_bound = "T: Decodable + Clone + 'static"
+    )]
+    enum Example10<T> {
+        #[byte_coding(value = 0)]
+        Empty,
+        #[byte_coding(value = 1)]
+        Items(Vec<T>),
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq, Clone)]
+    struct Example11 {
+        temperature: f32,
+        position: [f64; 2],
+        reading: Option<f64>,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq, Clone)]
+    struct Example12 {
+        initial: char,
+        id: NonZeroU32,
+        counter: Wrapping<u8>,
+        priority: Reverse<u16>,
+        marker: PhantomData<String>,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq, Clone)]
+    struct Example13 {
+        name: Cow<'static, str>,
+        shared: Arc<str>,
+        parent: Option<Arc<Example13>>,
+    }
+
+    #[derive(Encodable, BorrowDecodable, Debug, PartialEq)]
+    struct Example14<'a, T> {
+        name: &'a str,
+        #[byte_coding(order_no = 0)]
+        payload: Option<&'a [u8]>,
+        note: Cow<'a, str>,
+        body: T,
+    }
+
+    #[derive(Encodable, BorrowDecodable, Debug, PartialEq)]
+    #[byte_coding(encoding_type = "u8", inferred_values)]
+    enum Example15<'de> {
+        Empty,
+        Text(&'de str),
+        Pair { key: &'de str, value: u32 },
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq, Clone)]
+    struct Example16 {
+        children: Vec<Example16>,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    struct Example17(
+        #[byte_coding(order_no = 1)] u8,
+        #[byte_coding(order_no = 0)] u16,
+    );
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    struct Example19 {
+        #[byte_coding(varint)]
+        count: u64,
+        #[byte_coding(zigzag, order_no = 0)]
+        delta: i32,
+        fixed: u16,
+    }
+
+    #[derive(Encodable, BorrowDecodable, Debug, PartialEq)]
+    struct Example20<'a>(#[byte_coding(varint)] usize, &'a str);
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(inferred_values)]
+    enum Example18 {
+        Empty,
+        Values(Vec<u16>),
+        Named { id: u32 },
+    }
+
+    #[derive(Encodable, Decodable, FixedSize, Debug, PartialEq)]
+    struct Example21<T> {
+        id: u32,
+        #[byte_coding(order_no = 0)]
+        kind: Example22,
+        #[byte_coding(ignore)]
+        cached: String,
+        value: T,
+    }
+
+    #[derive(Encodable, Decodable, FixedSize, Debug, PartialEq)]
+    #[byte_coding(encoding_type = "u8", inferred_values, size = 5)]
+    enum Example22 {
+        Point(i16, i16),
+        Id { id: u32 },
+        Raw([u8; 4]),
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(version = 3)]
+    struct Example23 {
+        id: u16,
+        #[byte_coding(since = 2)]
+        name: String,
+        #[byte_coding(until = 1)]
+        legacy: bool,
+        #[byte_coding(since = 3, default = "default_priority")]
+        priority: u8,
+    }
+
+    #[derive(Encodable, BorrowDecodable, Debug, PartialEq)]
+    #[byte_coding(version = 2)]
+    struct Example24<'a>(&'a str, #[byte_coding(since = 2)] Option<&'a str>);
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(tagged)]
+    struct Example25 {
+        #[byte_coding(tag = 1)]
+        id: u16,
+        #[byte_coding(tag = 7, varint)]
+        count: u32,
+        #[byte_coding(tag = 3, order_no = 0)]
+        name: String,
+        #[byte_coding(ignore)]
+        cached: bool,
+    }
+
+    /// An older version of Example25 which only knows the name, and a field which was removed.
+    #[derive(Encodable, BorrowDecodable, Debug, PartialEq)]
+    #[byte_coding(tagged)]
+    struct Example26<'a>(
+        #[byte_coding(tag = 3)] &'a str,
+        #[byte_coding(tag = 2, default = "default_priority")] u8,
+    );
+
+    /// A type which does not implement the byte_coding traits.
+    #[derive(Debug, Default, PartialEq, Clone, Copy)]
+    struct Celsius(f32);
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    struct Example27 {
+        #[byte_coding(with = "celsius_codec")]
+        reading: Celsius,
+        #[byte_coding(encode_with = "encode_short_str", decode_with = "decode_short_str")]
+        label: String,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(encoding_type = "u8", inferred_values)]
+    enum Example28 {
+        Reading(#[byte_coding(with = "celsius_codec")] Celsius),
+        Range {
+            #[byte_coding(with = "celsius_codec")]
+            low: Celsius,
+            high: i16,
+        },
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(tagged)]
+    struct Example29(#[byte_coding(tag = 1, with = "celsius_codec")] Celsius);
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(encoding_type = "u8", inferred_values)]
+    enum Example30<T> {
+        Pair(
+            #[byte_coding(order_no = 1)] u8,
+            #[byte_coding(order_no = 0)] u16,
+            #[byte_coding(ignore)] String,
+        ),
+        Named {
+            #[byte_coding(ignore)]
+            cached: Vec<u8>,
+            #[byte_coding(varint)]
+            len: u32,
+            #[byte_coding(order_no = 0, zigzag)]
+            delta: i16,
+        },
+        // T is only used by an ignored field, so it doesn't need to be Encodable
+        Marker(#[byte_coding(ignore)] PhantomData<T>),
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    struct Example31 {
+        id: u16,
+        #[byte_coding(skip_encode)]
+        received_at: u32,
+        // NonZeroU32 does not implement Default
+        #[byte_coding(ignore, default = "default_generation")]
+        generation: NonZeroU32,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(post_dec_func = "compute_example32_checksum")]
+    struct Example32 {
+        data: Vec<u8>,
+        #[byte_coding(skip_decode)]
+        checksum: u8,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    struct Example33 {
+        #[byte_coding(present_if = "self.flags & 0x4 != 0")]
+        checksum: u16,
+        // Decoded first so the conditions can use it
+        #[byte_coding(order_no = 0)]
+        flags: u8,
+        #[byte_coding(present_if = "example33_has_name(self.flags)", default = "unnamed")]
+        name: String,
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    #[byte_coding(encoding_type = "u8", inferred_values)]
+    enum Example34 {
+        Short(u8, #[byte_coding(present_if = "self.0 > 1")] u16),
+        Long {
+            len: u8,
+            #[byte_coding(present_if = "self.len != 0", varint)]
+            extra: u32,
+        },
+    }
+
+    #[derive(Encodable, Decodable, Debug, PartialEq)]
+    struct Example35 {
+        #[byte_coding(count = "n_items")]
+        items: Vec<u16>,
+        #[byte_coding(byte_len = "payload_len", order_no = 2)]
+        payload: Vec<String>,
+        #[byte_coding(order_no = 0)]
+        n_items: u8,
+        #[byte_coding(order_no = 1, varint)]
+        payload_len: u32,
+    }
+
+    #[derive(Encodable, BorrowDecodable, Debug, PartialEq)]
+    #[byte_coding(encoding_type = "u8", inferred_values)]
+    enum Example36<'a> {
+        Text(u16, #[byte_coding(byte_len = "0")] &'a str),
+        Bytes {
+            len: u8,
+            #[byte_coding(count = "len")]
+            bytes: &'a [u8],
+        },
+    }
+
+    /// A tree whose children are nested tagged structs. The values are written by a function
+    /// which counts how often it is called.
+    #[derive(Encodable, Decodable, Clone, Debug, PartialEq)]
+    #[byte_coding(tagged)]
+    struct Example37 {
+        #[byte_coding(tag = 1, encode_with = "encode_example37_value")]
+        value: u8,
+        #[byte_coding(tag = 2)]
+        children: Vec<Example37>,
+    }
+
+    /// A tree whose children are stored after the number of bytes they use.
+    #[derive(Encodable, Decodable, Clone, Debug, PartialEq)]
+    struct Example38 {
+        len: u16,
+        #[byte_coding(encode_with = "encode_example38_value")]
+        value: u8,
+        #[byte_coding(byte_len = "len")]
+        children: Vec<Example38>,
+    }
+
+    static EXAMPLE37_ENCODED: AtomicUsize = AtomicUsize::new(0);
+    static EXAMPLE38_ENCODED: AtomicUsize = AtomicUsize::new(0);
+
+    fn encode_example37_value(value: &u8, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        EXAMPLE37_ENCODED.fetch_add(1, Ordering::Relaxed);
+
+        return value.encode_to(encoder);
+    }
+
+    fn encode_example38_value(value: &u8, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        EXAMPLE38_ENCODED.fetch_add(1, Ordering::Relaxed);
+
+        return value.encode_to(encoder);
+    }
+
+    fn example33_has_name(flags: u8) -> bool {
+        return flags & 0x1 != 0;
+    }
+
+    fn unnamed() -> String {
+        return "unnamed".to_string();
+    }
+
+    fn default_generation() -> NonZeroU32 {
+        return NonZeroU32::new(1).unwrap();
+    }
+
+    fn compute_example32_checksum(mut v: Example32, buffer: &[u8]) -> Option<(Example32, &[u8])> {
+        v.checksum = v.data.iter().fold(0, |sum, b| sum ^ b);
+
+        return Some((v, buffer));
+    }
+
+    /// Stores a temperature in tenths of a degree.
+    mod celsius_codec {
+        use super::Celsius;
+        use byte_coding::{Decodable, DecodeError, Decoder, Encodable, EncodeError, Encoder};
+
+        pub fn encode(value: &Celsius, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+            return ((value.0 * 10.0) as i16).encode_to(encoder);
+        }
+
+        pub fn decode(decoder: &mut Decoder<'_>) -> Result<Celsius, DecodeError> {
+            return Ok(Celsius(i16::decode_from(decoder)? as f32 / 10.0));
+        }
+    }
+
+    fn encode_short_str(value: &String, encoder: &mut Encoder<'_>) -> Result<(), EncodeError> {
+        (value.len() as u8).encode_to(encoder)?;
+
+        return encoder.write_bytes(value.as_bytes());
+    }
+
+    fn decode_short_str(decoder: &mut Decoder<'_>) -> Result<String, DecodeError> {
+        let len = u8::decode_from(decoder)?;
+
+        return String::from_utf8(decoder.read_byte_vec(len as usize)?).map_err(|e| {
+            DecodeError::InvalidUtf8 {
+                valid_up_to: e.utf8_error().valid_up_to(),
+            }
+        });
+    }
+
+    fn default_priority() -> u8 {
+        return 5;
+    }
+
     impl Example4 {
         fn make_f2_none(e4: &Example4) -> Example4 {
             return Example4 {
@@ -113,6 +474,7 @@ mod derive_tests {
         ));
     }
 
+    #[allow(clippy::len_zero)]
     fn undo_append_data_e2(buffer: &[u8]) -> Option<&[u8]> {
         if buffer.len() == 0 || buffer[buffer.len() - 1] != b'h' {
             return None;
@@ -204,6 +566,20 @@ mod derive_tests {
             assert_eq!(value.encoded(), vec![2, 0, 0, 0, 0, 0, 0, 0, b'f', b'1']);
         }
 
+        #[test]
+        fn test_example11_encoding() {
+            let value = Example11 {
+                temperature: -2.5,
+                position: [0.5, -0.0],
+                reading: None,
+            };
+
+            assert_eq!(
+                value.encoded(),
+                vec![0, 0, 0x20, 0xc0, 0, 0, 0, 0, 0, 0, 0xe0, 0x3f, 0, 0, 0, 0, 0, 0, 0, 0x80, 0]
+            );
+        }
+
         #[test]
         fn test_example7_encoding_1() {
             let value = Example7::V1;
@@ -231,6 +607,298 @@ mod derive_tests {
 
             assert_eq!(value.encoded(), vec![167, 2]);
         }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_example2_encoding_sink() {
+            let example2 = Example2 {
+                a: "test".to_string(),
+                b: "dogs".to_string(),
+            };
+            let mut sink = WriteSink::new(Vec::new());
+
+            (1u8, example2).encode_to_sink(&mut sink).unwrap();
+
+            // The post_enc_func only sees the bytes of the Example2 value
+            assert_eq!(
+                sink.into_inner(),
+                vec![
+                    1, 4, 0, 0, 0, 0, 0, 0, 0, b'd', b'o', b'g', b's', 4, 0, 0, 0, 0, 0, 0, 0,
+                    b'c', b'o', b'w', b's', b'h'
+                ]
+            );
+        }
+
+        #[test]
+        fn test_example18_encoding_errors() {
+            let value = Example18::Values(vec![1, 2]);
+            let mut storage = [0u8; 12];
+            let mut sink = &mut storage[..];
+
+            assert_eq!(
+                value.encode_to_sink(&mut sink),
+                Err(EncodeError::BufferFull {
+                    needed: 2,
+                    available: 0
+                })
+            );
+
+            let config = Config::new().with_length_encoding(IntEncoding::U8);
+            let value = Example18::Values(vec![0; 256]);
+
+            assert_eq!(
+                value.encoded_with_config(config),
+                Err(EncodeError::IntegerTooLarge {
+                    value: 256,
+                    max: 255
+                })
+            );
+        }
+
+        #[test]
+        fn test_example21_fixed_size() {
+            let value = Example21 {
+                id: 1,
+                kind: Example22::Point(-1, 2),
+                cached: "not encoded".to_string(),
+                value: 3u64,
+            };
+
+            assert_eq!(Example22::SIZE, 5);
+            assert_eq!(Example21::<u64>::SIZE, 17);
+            assert_eq!(Example21::<[bool; 9]>::SIZE, 9 + <[bool; 9]>::SIZE);
+            assert_eq!(value.encoded_len(), Example21::<u64>::SIZE);
+
+            let mut buf = [0u8; Example21::<u64>::SIZE];
+
+            assert_eq!(value.encode_to_slice(&mut buf), Ok(17));
+            assert_eq!(buf, [0, 255, 255, 2, 0, 1, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+            assert_eq!(Example22::Id { id: 7 }.encoded_len(), Example22::SIZE);
+            assert_eq!(Example22::Raw([1; 4]).encoded_len(), Example22::SIZE);
+        }
+
+        #[test]
+        fn test_versioned_encoding() {
+            let value = Example23 {
+                id: 1,
+                name: "a".to_string(),
+                legacy: true,
+                priority: 2,
+            };
+
+            // The legacy field is no longer written
+            assert_eq!(
+                value.encoded(),
+                vec![3, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 2]
+            );
+            assert_eq!(
+                Example24("a", Some("b")).encoded(),
+                vec![2, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 1, 1, 0, 0, 0, 0, 0, 0, 0, b'b']
+            );
+        }
+
+        #[test]
+        fn test_custom_codec_encoding() {
+            let value = Example27 {
+                reading: Celsius(21.5),
+                label: "ab".to_string(),
+            };
+
+            assert_eq!(value.encoded(), vec![215, 0, 2, b'a', b'b']);
+            assert_eq!(
+                Example28::Reading(Celsius(-1.5)).encoded(),
+                vec![0, 241, 255]
+            );
+            assert_eq!(
+                Example28::Range {
+                    low: Celsius(1.0),
+                    high: 2
+                }
+                .encoded(),
+                vec![1, 10, 0, 2, 0]
+            );
+            assert_eq!(Example29(Celsius(1.0)).encoded(), vec![1, 1, 2, 10, 0]);
+        }
+
+        #[test]
+        fn test_variant_field_attr_encoding() {
+            assert_eq!(
+                Example30::<Celsius>::Pair(1, 2, "a".to_string()).encoded(),
+                vec![0, 2, 0, 1]
+            );
+            assert_eq!(
+                Example30::<Celsius>::Named {
+                    cached: vec![1],
+                    len: 300,
+                    delta: -1,
+                }
+                .encoded(),
+                vec![1, 1, 0xac, 2]
+            );
+            assert_eq!(Example30::<Celsius>::Marker(PhantomData).encoded(), vec![2]);
+        }
+
+        #[test]
+        fn test_skipped_field_encoding() {
+            let value = Example31 {
+                id: 1,
+                received_at: 2,
+                generation: NonZeroU32::new(3).unwrap(),
+            };
+
+            assert_eq!(value.encoded(), vec![1, 0]);
+
+            let value = Example32 {
+                data: vec![1, 2],
+                checksum: 3,
+            };
+
+            assert_eq!(value.encoded(), vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3]);
+        }
+
+        #[test]
+        fn test_conditional_encoding() {
+            let value = Example33 {
+                checksum: 2,
+                flags: 0x5,
+                name: "a".to_string(),
+            };
+
+            assert_eq!(value.encoded(), vec![5, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0, b'a']);
+
+            let value = Example33 {
+                checksum: 2,
+                flags: 0,
+                name: "a".to_string(),
+            };
+
+            assert_eq!(value.encoded(), vec![0]);
+            assert_eq!(Example34::Short(1, 2).encoded(), vec![0, 1]);
+            assert_eq!(Example34::Short(2, 3).encoded(), vec![0, 2, 3, 0]);
+            assert_eq!(
+                Example34::Long { len: 1, extra: 300 }.encoded(),
+                vec![1, 1, 0xac, 2]
+            );
+        }
+
+        #[test]
+        fn test_length_field_encoding() {
+            let value = Example35 {
+                items: vec![1, 2],
+                payload: vec!["a".to_string()],
+                n_items: 0,
+                payload_len: 0,
+            };
+
+            // The length fields are written from the lengths, not their own values
+            assert_eq!(
+                value.encoded(),
+                vec![2, 9, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 1, 0, 2, 0]
+            );
+            assert_eq!(
+                Example36::Text(0, "ab").encoded(),
+                vec![0, 2, 0, b'a', b'b']
+            );
+            assert_eq!(
+                Example36::Bytes { len: 0, bytes: &[] }.encoded(),
+                vec![1, 0]
+            );
+
+            let value = Example35 {
+                items: vec![0; 256],
+                payload: Vec::new(),
+                n_items: 0,
+                payload_len: 0,
+            };
+
+            assert_eq!(
+                value.encoded_with_config(Config::default()),
+                Err(EncodeError::LengthFieldOverflow { length: 256 })
+            );
+
+            let text = "a".repeat(65536);
+
+            assert_eq!(
+                Example36::Text(0, &text).encoded_with_config(Config::default()),
+                Err(EncodeError::LengthFieldOverflow { length: 65536 })
+            );
+        }
+
+        #[test]
+        fn test_nested_encoding() {
+            let mut tree37 = Example37 {
+                value: 4,
+                children: Vec::new(),
+            };
+            let mut tree38 = Example38 {
+                len: 0,
+                value: 4,
+                children: Vec::new(),
+            };
+
+            for value in (0..4).rev() {
+                tree37 = Example37 {
+                    value,
+                    children: vec![tree37],
+                };
+                tree38 = Example38 {
+                    len: 0,
+                    value,
+                    children: vec![tree38],
+                };
+            }
+
+            // Each of the 5 levels is only encoded once
+            let encoded = tree37.encoded();
+
+            assert_eq!(EXAMPLE37_ENCODED.load(Ordering::Relaxed), 5);
+            assert_eq!(
+                encoded[..8],
+                [2, 1, 1, 0, 2, 64, 1, 0],
+                "2 fields, the value, and the children which use 64 bytes"
+            );
+            assert_eq!(Example37::decode(&encoded), Ok(tree37));
+
+            let encoded = tree38.encoded();
+
+            assert_eq!(EXAMPLE38_ENCODED.load(Ordering::Relaxed), 5);
+            assert_eq!(encoded[..4], [12, 0, 0, 9]);
+            assert_eq!(encoded[encoded.len() - 3..], [0, 0, 4]);
+
+            let mut decoded = Example38::decode(&encoded).unwrap();
+            let mut len = encoded.len();
+
+            // The length fields were written from the lengths of the children
+            for value in 0..5 {
+                len -= 3;
+                assert_eq!((decoded.len as usize, decoded.value), (len, value));
+
+                decoded = match decoded.children.pop() {
+                    Some(child) => child,
+                    None => break,
+                };
+            }
+        }
+
+        #[test]
+        fn test_tagged_encoding() {
+            let value = Example25 {
+                id: 1,
+                count: 300,
+                name: "a".to_string(),
+                cached: true,
+            };
+
+            // The field count, then the tag, length and value of each field in order_no order
+            assert_eq!(
+                value.encoded(),
+                vec![3, 3, 9, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 1, 2, 1, 0, 7, 2, 0xac, 2]
+            );
+            assert_eq!(
+                Example26("a", 4).encoded(),
+                vec![2, 3, 9, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 2, 1, 4]
+            );
+        }
     }
 
     mod decoding {
@@ -329,6 +997,28 @@ mod derive_tests {
             );
         }
 
+        #[test]
+        fn test_example6_decoding() {
+            let value = Example6 {
+                v1: 12,
+                v2: Example6Base {
+                    e6_1: Example6Enum::A2,
+                    e6_2: Example6Enum::A3,
+                },
+                v3: "test".to_string(),
+                v4: vec![Example6Enum::A1, Example6Enum::A3],
+                v5: vec![Example6Base {
+                    e6_1: Example6Enum::A3,
+                    e6_2: Example6Enum::A1,
+                }],
+            };
+
+            let encoded = value.encoded();
+            let decoded: Example6 = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(value, decoded);
+        }
+
         #[test]
         fn test_example7_decoding_1() {
             let value = Example7::V1;
@@ -364,5 +1054,694 @@ mod derive_tests {
 
             assert_eq!(value, decoded);
         }
+
+        #[test]
+        fn test_example8_decoding() {
+            let value = Example8 {
+                id: 7,
+                body: Example1("test".to_string()),
+            };
+            let encoded = value.encoded();
+
+            assert_eq!(
+                encoded,
+                vec![7, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, b't', b'e', b's', b't']
+            );
+
+            let decoded: Example8<Example1> = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(value, decoded);
+        }
+
+        #[test]
+        fn test_example9_decoding() {
+            let value = Example9 {
+                data: [1, 2, 3],
+                value: Some(12u16),
+                label: Some("label"),
+            };
+            let encoded = value.encoded();
+
+            assert_eq!(encoded, vec![1, 2, 3, 1, 12, 0]);
+
+            let decoded: Example9<u16, 3> = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(
+                decoded,
+                Example9 {
+                    data: [1, 2, 3],
+                    value: Some(12),
+                    label: None,
+                }
+            );
+        }
+
+        #[test]
+        fn test_example10_decoding() {
+            let value = Example10::Items(vec![4u64, 5]);
+            let encoded = value.encoded();
+            let decoded: Example10<u64> = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(value, decoded);
+        }
+
+        #[test]
+        fn test_example11_decoding() {
+            let value = Example11 {
+                temperature: 21.75,
+                position: [-0.0, f64::INFINITY],
+                reading: Some(f64::NAN),
+            };
+
+            let encoded = value.encoded();
+            let decoded: Example11 = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(decoded.temperature, 21.75);
+            assert_eq!(decoded.position[0].to_bits(), (-0.0f64).to_bits());
+            assert_eq!(decoded.position[1], f64::INFINITY);
+            assert_eq!(decoded.reading.map(f64::to_bits), Some(f64::NAN.to_bits()));
+        }
+
+        #[test]
+        fn test_example12_decoding() {
+            let value = Example12 {
+                initial: 'z',
+                id: NonZeroU32::new(9).unwrap(),
+                counter: Wrapping(255),
+                priority: Reverse(3),
+                marker: PhantomData,
+            };
+
+            let encoded = value.encoded();
+
+            assert_eq!(encoded, vec![b'z', 0, 0, 0, 9, 0, 0, 0, 255, 3, 0]);
+
+            let decoded: Example12 = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(value, decoded);
+            assert_eq!(
+                Example12::decode(&[b'z', 0, 0, 0, 0, 0, 0, 0, 255, 3, 0]),
+                Err(DecodeError::InvalidNonZero)
+            );
+        }
+
+        #[test]
+        fn test_example13_decoding() {
+            let parent = Arc::new(Example13 {
+                name: Cow::Borrowed("root"),
+                shared: Arc::from("config"),
+                parent: None,
+            });
+            let value = Example13 {
+                name: Cow::Owned("child".to_string()),
+                shared: parent.shared.clone(),
+                parent: Some(parent),
+            };
+
+            let encoded = value.encoded();
+            let decoded: Example13 = Decodable::decode(&encoded).unwrap();
+
+            assert_eq!(value, decoded);
+        }
+
+        #[test]
+        fn test_unknown_enum_tag() {
+            let res = Example3::decode(&[9, 0]);
+
+            assert_eq!(res, Err(DecodeError::UnknownEnumTag { tag: 9 }));
+        }
+
+        #[test]
+        fn test_truncated_struct() {
+            let res = Example3::decode(&[3, 0, 100, 0, 0, 0, 200]);
+
+            assert_eq!(
+                res,
+                Err(DecodeError::UnexpectedEnd {
+                    needed: 8,
+                    available: 1
+                })
+            );
+        }
+
+        #[test]
+        fn test_pre_dec_func_failure() {
+            let mut encoded = Example2 {
+                a: "test".to_string(),
+                b: "dogs".to_string(),
+            }
+            .encoded();
+            encoded.pop();
+
+            assert_eq!(
+                Example2::decode(&encoded),
+                Err(DecodeError::HookFailed {
+                    hook: "undo_append_data_e2"
+                })
+            );
+        }
+
+        #[test]
+        fn test_example14_borrow_decoding() {
+            let value = Example14 {
+                name: "name",
+                payload: Some(&[1, 2, 3]),
+                note: Cow::Owned("note".to_string()),
+                body: 7u16,
+            };
+
+            let encoded = value.encoded();
+            let decoded: Example14<u16> = BorrowDecodable::borrow_decode(&encoded).unwrap();
+
+            assert_eq!(value, decoded);
+            assert!(matches!(decoded.note, Cow::Borrowed("note")));
+            assert_eq!(decoded.payload.unwrap().as_ptr(), encoded[9..].as_ptr());
+        }
+
+        #[test]
+        fn test_example15_borrow_decoding() {
+            for value in [
+                Example15::Empty,
+                Example15::Text("text"),
+                Example15::Pair {
+                    key: "key",
+                    value: 5,
+                },
+            ] {
+                let encoded = value.encoded();
+                let decoded: Example15 = BorrowDecodable::borrow_decode(&encoded).unwrap();
+
+                assert_eq!(value, decoded);
+            }
+
+            assert_eq!(
+                Example15::borrow_decode(&[3]),
+                Err(DecodeError::UnknownEnumTag { tag: 3 })
+            );
+        }
+
+        #[test]
+        fn test_example16_depth_limit() {
+            let mut value = Example16 { children: vec![] };
+
+            for _ in 0..10 {
+                value = Example16 {
+                    children: vec![value],
+                };
+            }
+
+            let encoded = value.encoded();
+
+            assert_eq!(Example16::decode(&encoded), Ok(value));
+            assert_eq!(
+                Example16::decode_with_limits(&encoded, DecodeLimits::new().with_max_depth(16)),
+                Err(DecodeError::DepthLimit { max: 16 })
+            );
+        }
+
+        #[test]
+        fn test_example17_decoding() {
+            let value = Example17(1, 2);
+            let encoded = value.encoded();
+
+            assert_eq!(encoded, vec![2, 0, 1]);
+            assert_eq!(Example17::decode(&encoded), Ok(value));
+        }
+
+        #[test]
+        fn test_example18_config() {
+            let config = Config::new()
+                .with_endian(Endian::Big)
+                .with_length_encoding(IntEncoding::VarInt)
+                .with_enum_tag_encoding(IntEncoding::U8);
+
+            let value = Example18::Values(vec![1, 2]);
+            let encoded = value.encoded_with_config(config).unwrap();
+
+            assert_eq!(encoded, vec![1, 2, 0, 1, 0, 2]);
+            assert_eq!(Example18::decode_with_config(&encoded, config), Ok(value));
+
+            let value = Example18::Named { id: 3 };
+            let encoded = value.encoded_with_config(config).unwrap();
+
+            assert_eq!(encoded, vec![2, 0, 0, 0, 3]);
+            assert_eq!(Example18::decode_with_config(&encoded, config), Ok(value));
+            assert_eq!(Example18::Empty.encoded(), vec![0, 0]);
+
+            // An explicit encoding type is not affected by the enum tag encoding
+            let config = Config::new().with_enum_tag_encoding(IntEncoding::U32);
+
+            assert_eq!(
+                Example15::Empty.encoded_with_config(config).unwrap(),
+                vec![0]
+            );
+        }
+
+        #[test]
+        fn test_example19_decoding() {
+            let value = Example19 {
+                count: 300,
+                delta: -2,
+                fixed: 1,
+            };
+            let encoded = value.encoded();
+
+            assert_eq!(encoded, vec![3, 0xac, 2, 1, 0]);
+            assert_eq!(Example19::decode(&encoded), Ok(value));
+            assert_eq!(
+                Example19::decode(&[3, 0x81, 0, 1, 0]),
+                Err(DecodeError::InvalidVarInt)
+            );
+        }
+
+        #[test]
+        fn test_example20_borrow_decoding() {
+            let value = Example20(5, "a");
+            let encoded = value.encoded();
+
+            assert_eq!(encoded, vec![5, 1, 0, 0, 0, 0, 0, 0, 0, b'a']);
+            assert_eq!(BorrowDecodable::borrow_decode(&encoded), Ok(value));
+        }
+
+        /// A reader which returns at most one byte from each call to `read`.
+        #[cfg(feature = "std")]
+        struct ByteReader<'a>(&'a [u8]);
+
+        #[cfg(feature = "std")]
+        impl std::io::Read for ByteReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.is_empty() || buf.is_empty() {
+                    return Ok(0);
+                }
+
+                buf[0] = self.0[0];
+                self.0 = &self.0[1..];
+
+                return Ok(1);
+            }
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_reader_decoding() {
+            macro_rules! check_type {
+                ($($value:expr => $ty:ty),+ $(,)?) => {
+                    $(
+                        let value = $value;
+                        let mut encoded = value.encoded();
+
+                        // Trailing bytes are left in the reader
+                        encoded.push(0xff);
+
+                        let mut reader = ByteReader(&encoded);
+                        assert_eq!(<$ty>::decode_from_reader(&mut reader), Ok(value));
+                        assert_eq!(reader.0, &[0xff]);
+
+                        for len in 0..encoded.len() - 1 {
+                            assert!(
+                                <$ty>::decode_from_reader(ByteReader(&encoded[..len])).is_err(),
+                                "{} decoded from {} of {} bytes",
+                                stringify!($ty),
+                                len,
+                                encoded.len() - 1
+                            );
+                        }
+                    )+
+                };
+            }
+
+            check_type!(
+                Example1("test".to_string()) => Example1,
+                Example3::A3 { f1: 1, f2: 2 } => Example3,
+                Example6 {
+                    v1: 1,
+                    v2: Example6Base { e6_1: Example6Enum::A1, e6_2: Example6Enum::A3 },
+                    v3: "test".to_string(),
+                    v4: vec![Example6Enum::A2],
+                    v5: vec![Example6Base { e6_1: Example6Enum::A2, e6_2: Example6Enum::A1 }],
+                } => Example6,
+                Example9::<u16, 3> { data: [1, 2, 3], value: Some(4), label: None } => Example9<u16, 3>,
+                Example10::Items(vec![true, false]) => Example10<bool>,
+                Example13 { name: "a".into(), shared: Arc::from("b"), parent: None } => Example13,
+                Example19 { count: u64::MAX, delta: -1, fixed: 2 } => Example19,
+                Example25 { id: 1, count: 300, name: "a".to_string(), cached: false } => Example25,
+            );
+
+            // The decode hooks operate on a buffer
+            let value = Example2 {
+                a: "test".to_string(),
+                b: "dogs".to_string(),
+            };
+
+            assert_eq!(
+                Example2::decode_from_reader(ByteReader(&value.encoded())),
+                Err(DecodeError::RequiresBuffer)
+            );
+        }
+
+        /// Feeds random buffers and every truncation of a valid encoding to each derived type.
+        /// Decoding may fail but must never panic, and a truncated encoding must be rejected.
+        #[test]
+        fn test_hostile_input() {
+            let mut state = 0x9e3779b97f4a7c15u64;
+            let mut next = move || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+
+                return state;
+            };
+
+            let random: Vec<Vec<u8>> = (0..2000)
+                .map(|_| {
+                    let len = (next() % 64) as usize;
+
+                    return (0..len)
+                        .map(|i| {
+                            if i % 8 == 0 {
+                                next() as u8 % 4
+                            } else {
+                                next() as u8
+                            }
+                        })
+                        .collect();
+                })
+                .collect();
+
+            macro_rules! check_type {
+                ($($value:expr => $ty:ty),+ $(,)?) => {
+                    $(
+                        let encoded = $value.encoded();
+
+                        assert!(<$ty>::decode(&encoded).is_ok());
+
+                        for len in 0..encoded.len() {
+                            assert!(
+                                <$ty>::decode(&encoded[..len]).is_err(),
+                                "{} decoded from {} of {} bytes",
+                                stringify!($ty),
+                                len,
+                                encoded.len()
+                            );
+                        }
+
+                        for buffer in random.iter() {
+                            let _ = <$ty>::decode(buffer);
+                        }
+                    )+
+                };
+            }
+
+            check_type!(
+                Example1("test".to_string()) => Example1,
+                Example2 { a: "test".to_string(), b: "dogs".to_string() } => Example2,
+                Example3::A3 { f1: 1, f2: 2 } => Example3,
+                Example3::A4(1, 2) => Example3,
+                Example4 { f1: "a".to_string(), f2: None } => Example4,
+                Example5 { f1: "a".to_string(), f2: None } => Example5,
+                Example6 {
+                    v1: 1,
+                    v2: Example6Base { e6_1: Example6Enum::A1, e6_2: Example6Enum::A3 },
+                    v3: "test".to_string(),
+                    v4: vec![Example6Enum::A2],
+                    v5: vec![Example6Base { e6_1: Example6Enum::A2, e6_2: Example6Enum::A1 }],
+                } => Example6,
+                Example7::V4 => Example7,
+                Example8 { id: 1, body: "body".to_string() } => Example8<String>,
+                Example9::<u16, 3> { data: [1, 2, 3], value: Some(4), label: None } => Example9<u16, 3>,
+                Example10::Items(vec![true, false]) => Example10<bool>,
+                Example11 { temperature: 1.0, position: [2.0, 3.0], reading: Some(4.0) } => Example11,
+                Example12 {
+                    initial: 'a',
+                    id: NonZeroU32::new(1).unwrap(),
+                    counter: Wrapping(2),
+                    priority: Reverse(3),
+                    marker: PhantomData,
+                } => Example12,
+                Example13 { name: "a".into(), shared: Arc::from("b"), parent: None } => Example13,
+                Example19 { count: u64::MAX, delta: -1, fixed: 2 } => Example19,
+                Example23 { id: 1, name: "a".to_string(), legacy: false, priority: 2 } => Example23,
+                Example25 { id: 1, count: 300, name: "a".to_string(), cached: false } => Example25,
+                Example33 { checksum: 2, flags: 0x5, name: "a".to_string() } => Example33,
+            );
+
+            for buffer in random.iter() {
+                let _ = Example14::<u16>::borrow_decode(buffer);
+                let _ = Example15::borrow_decode(buffer);
+            }
+        }
+
+        #[test]
+        fn test_versioned_decoding() {
+            assert_eq!(
+                Example23::decode(&[1, 1, 0, 1]),
+                Ok(Example23 {
+                    id: 1,
+                    name: String::new(),
+                    legacy: true,
+                    priority: 5,
+                })
+            );
+            assert_eq!(
+                Example23::decode(&[2, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, b'a']),
+                Ok(Example23 {
+                    id: 1,
+                    name: "a".to_string(),
+                    legacy: false,
+                    priority: 5,
+                })
+            );
+            assert_eq!(
+                Example23::decode(&[3, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 2]),
+                Ok(Example23 {
+                    id: 1,
+                    name: "a".to_string(),
+                    legacy: false,
+                    priority: 2,
+                })
+            );
+            assert_eq!(
+                Example23::decode(&[4, 1, 0]),
+                Err(DecodeError::UnsupportedVersion { version: 4, max: 3 })
+            );
+
+            let encoded = [1, 1, 0, 0, 0, 0, 0, 0, 0, b'a'];
+            assert_eq!(Example24::borrow_decode(&encoded), Ok(Example24("a", None)));
+
+            let encoded = Example24("a", Some("b")).encoded();
+            assert_eq!(
+                Example24::borrow_decode(&encoded),
+                Ok(Example24("a", Some("b")))
+            );
+        }
+
+        #[test]
+        fn test_tagged_decoding() {
+            let value = Example25 {
+                id: 1,
+                count: 300,
+                name: "a".to_string(),
+                cached: false,
+            };
+            let encoded = value.encoded();
+
+            assert_eq!(Example25::decode(&encoded), Ok(value));
+
+            // Fields can be in any order and missing fields use their default
+            assert_eq!(
+                Example25::decode(&[2, 7, 1, 5, 1, 2, 4, 0]),
+                Ok(Example25 {
+                    id: 4,
+                    count: 5,
+                    name: String::new(),
+                    cached: false,
+                })
+            );
+
+            // A field can't be written twice
+            assert_eq!(
+                Example25::decode(&[2, 1, 2, 4, 0, 1, 2, 5, 0]),
+                Err(DecodeError::DuplicateTag { tag: 1 })
+            );
+
+            // Unknown tags are skipped, from a buffer or a reader
+            let encoded = [2, 9, 3, 1, 2, 3, 1, 2, 4, 0];
+            let expected = Example25 {
+                id: 4,
+                count: 0,
+                name: String::new(),
+                cached: false,
+            };
+
+            assert_eq!(Example25::decode(&encoded), Ok(expected));
+            #[cfg(feature = "std")]
+            assert_eq!(
+                Example25::decode_from_reader(ByteReader(&encoded)).map(|v| v.id),
+                Ok(4)
+            );
+
+            // An older reader skips the fields it does not know
+            let encoded = Example25 {
+                id: 1,
+                count: 2,
+                name: "a".to_string(),
+                cached: true,
+            }
+            .encoded();
+
+            assert_eq!(Example26::borrow_decode(&encoded), Ok(Example26("a", 5)));
+
+            // A value can't be longer than its length
+            assert_eq!(
+                Example25::decode(&[1, 1, 1, 4, 0]),
+                Err(DecodeError::UnexpectedEnd {
+                    needed: 2,
+                    available: 1
+                })
+            );
+            assert_eq!(
+                Example25::decode(&[1, 1, 3, 4, 0]),
+                Err(DecodeError::UnexpectedEnd {
+                    needed: 3,
+                    available: 2
+                })
+            );
+        }
+
+        #[test]
+        fn test_custom_codec_decoding() {
+            assert_eq!(
+                Example27::decode(&[215, 0, 2, b'a', b'b']),
+                Ok(Example27 {
+                    reading: Celsius(21.5),
+                    label: "ab".to_string(),
+                })
+            );
+            assert_eq!(
+                Example27::decode(&[215, 0, 1, 0xff]),
+                Err(DecodeError::InvalidUtf8 { valid_up_to: 0 })
+            );
+            assert_eq!(
+                Example28::decode(&[0, 241, 255]),
+                Ok(Example28::Reading(Celsius(-1.5)))
+            );
+            assert_eq!(
+                Example28::decode(&[1, 10, 0, 2, 0]),
+                Ok(Example28::Range {
+                    low: Celsius(1.0),
+                    high: 2
+                })
+            );
+            assert_eq!(
+                Example29::decode(&[1, 1, 2, 10, 0]),
+                Ok(Example29(Celsius(1.0)))
+            );
+        }
+
+        #[test]
+        fn test_variant_field_attr_decoding() {
+            assert_eq!(
+                Example30::<Celsius>::decode(&[0, 2, 0, 1]),
+                Ok(Example30::Pair(1, 2, String::new()))
+            );
+            assert_eq!(
+                Example30::<Celsius>::decode(&[1, 1, 0xac, 2]),
+                Ok(Example30::Named {
+                    cached: Vec::new(),
+                    len: 300,
+                    delta: -1,
+                })
+            );
+            assert_eq!(
+                Example30::<Celsius>::decode(&[2]),
+                Ok(Example30::Marker(PhantomData))
+            );
+        }
+
+        #[test]
+        fn test_skipped_field_decoding() {
+            assert_eq!(
+                Example31::decode(&[1, 0, 2, 0, 0, 0]),
+                Ok(Example31 {
+                    id: 1,
+                    received_at: 2,
+                    generation: NonZeroU32::new(1).unwrap(),
+                })
+            );
+
+            // The checksum is computed rather than read
+            assert_eq!(
+                Example32::decode(&[2, 0, 0, 0, 0, 0, 0, 0, 1, 2]),
+                Ok(Example32 {
+                    data: vec![1, 2],
+                    checksum: 3,
+                })
+            );
+        }
+
+        #[test]
+        fn test_conditional_decoding() {
+            assert_eq!(
+                Example33::decode(&[5, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0, b'a']),
+                Ok(Example33 {
+                    checksum: 2,
+                    flags: 0x5,
+                    name: "a".to_string(),
+                })
+            );
+
+            // Absent fields use their default
+            assert_eq!(
+                Example33::decode(&[4, 2, 0]),
+                Ok(Example33 {
+                    checksum: 2,
+                    flags: 0x4,
+                    name: "unnamed".to_string(),
+                })
+            );
+            assert_eq!(Example34::decode(&[0, 1]), Ok(Example34::Short(1, 0)));
+            assert_eq!(Example34::decode(&[0, 2, 3, 0]), Ok(Example34::Short(2, 3)));
+            assert_eq!(
+                Example34::decode(&[1, 0]),
+                Ok(Example34::Long { len: 0, extra: 0 })
+            );
+            assert_eq!(
+                Example34::decode(&[1, 1, 0xac, 2]),
+                Ok(Example34::Long { len: 1, extra: 300 })
+            );
+        }
+
+        #[test]
+        fn test_length_field_decoding() {
+            assert_eq!(
+                Example35::decode(&[2, 9, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 1, 0, 2, 0]),
+                Ok(Example35 {
+                    items: vec![1, 2],
+                    payload: vec!["a".to_string()],
+                    n_items: 2,
+                    payload_len: 9,
+                })
+            );
+
+            // The payload length ends part way through the string
+            assert_eq!(
+                Example35::decode(&[0, 8, 1, 0, 0, 0, 0, 0, 0, 0, b'a']),
+                Err(DecodeError::UnexpectedEnd {
+                    needed: 1,
+                    available: 0
+                })
+            );
+            assert_eq!(
+                Example36::borrow_decode(&[0, 2, 0, b'a', b'b']),
+                Ok(Example36::Text(2, "ab"))
+            );
+            assert_eq!(
+                Example36::borrow_decode(&[1, 3, 1, 2, 3]),
+                Ok(Example36::Bytes {
+                    len: 3,
+                    bytes: &[1, 2, 3]
+                })
+            );
+        }
     }
 }
@@ -0,0 +1,90 @@
+#![allow(clippy::needless_return)]
+
+/// Only uses the parts of the crate which are available without the `alloc` feature.
+#[cfg(feature = "derive")]
+mod no_alloc_tests {
+    use byte_coding::*;
+
+    #[derive(Encodable, Decodable, BorrowDecodable, Debug, PartialEq)]
+    struct Reading {
+        #[byte_coding(order_no = 0)]
+        id: u16,
+        #[byte_coding(varint)]
+        ticks: u64,
+        samples: [i16; 3],
+        flags: [bool; 10],
+    }
+
+    #[derive(Encodable, BorrowDecodable, Debug, PartialEq)]
+    #[byte_coding(inferred_values)]
+    enum Message<'a> {
+        Ping,
+        Text(&'a str),
+        Reading(Reading),
+    }
+
+    #[test]
+    fn test_encode_to_slice() {
+        let value = Reading {
+            id: 7,
+            ticks: 300,
+            samples: [-1, 0, 1],
+            flags: [true; 10],
+        };
+        let mut buf = [0u8; 32];
+        let len = value.encode_to_slice(&mut buf).unwrap();
+
+        assert_eq!(&buf[..10], &[7, 0, 0xac, 2, 0xff, 0xff, 0, 0, 1, 0]);
+
+        if cfg!(feature = "bool_arr_optimization") {
+            assert_eq!(&buf[10..len], &[0xff, 0x03]);
+        } else {
+            assert_eq!(&buf[10..len], &[1; 10]);
+        }
+
+        assert_eq!(Reading::decode(&buf[..len]), Ok(value));
+        assert_eq!(
+            Reading::decode(&buf[..9]),
+            Err(DecodeError::UnexpectedEnd {
+                needed: 2,
+                available: 1
+            })
+        );
+        assert!(Reading::decode(&buf[..len - 1]).is_err());
+    }
+
+    #[test]
+    fn test_buffer_full() {
+        let value = Message::Text("too long");
+        let mut buf = [0u8; 12];
+
+        assert_eq!(
+            value.encode_to_slice(&mut buf),
+            Err(EncodeError::BufferFull {
+                needed: 8,
+                available: 2
+            })
+        );
+        assert_eq!(Message::Ping.encode_to_slice(&mut buf), Ok(2));
+    }
+
+    #[test]
+    fn test_borrow_decoding() {
+        let mut buf = [0u8; 32];
+
+        for value in [
+            Message::Ping,
+            Message::Text("text"),
+            Message::Reading(Reading {
+                id: 1,
+                ticks: 2,
+                samples: [3, 4, 5],
+                flags: [false; 10],
+            }),
+        ] {
+            let len = value.encode_to_slice(&mut buf).unwrap();
+
+            assert_eq!(Message::borrow_decode(&buf[..len]), Ok(value));
+        }
+    }
+}